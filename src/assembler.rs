@@ -0,0 +1,390 @@
+use crate::opcodes::{Opcode, OPCODES};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// How deeply `:include` may nest, which catches files including themselves.
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+/// The operand fields of the opcode table's syntax, see `Opcode`.
+const PLACEHOLDERS: [&str; 7] = ["Vx", "Vy", "nnnn", "nnn", "nn", "n", "x"];
+
+/// Operand tokens of the opcode table that are not placeholders.
+const KEYWORDS: [&str; 11] = ["I", "[I]", "DT", "ST", "K", "F", "HF", "B", "R", "LONG", "-"];
+
+struct SourceLine {
+    file: String,
+    number: usize,
+    text: String,
+}
+
+enum Statement {
+    /// An opcode with its placeholders bound to register numbers or to
+    /// values that may refer to labels defined further down.
+    Instruction(&'static Opcode, Vec<(&'static str, Operand)>),
+    Bytes(Vec<String>),
+    Words(Vec<String>),
+}
+
+enum Operand {
+    Register(u16),
+    Value(String),
+}
+
+/// Assembles source text into a ROM loaded at `start`. `:include` paths are
+/// relative to the current directory.
+pub fn assemble(source: &str, start: u16) -> Result<Vec<u8>, String> {
+    let mut lines = Vec::new();
+    read_lines(source, "input", Path::new("."), 0, &mut lines)?;
+
+    return Assembler::new(start).run(&lines);
+}
+
+/// Assembles a source file, resolving `:include` relative to it.
+pub fn assemble_file(path: &Path, start: u16) -> Result<Vec<u8>, String> {
+    let mut lines = Vec::new();
+    read_file(path, 0, &mut lines)?;
+
+    return Assembler::new(start).run(&lines);
+}
+
+fn read_file(path: &Path, depth: usize, lines: &mut Vec<SourceLine>) -> Result<(), String> {
+    let source = fs::read_to_string(path).map_err(|e| format!("Could not read '{}': {}", path.display(), e))?;
+    let directory = path.parent().unwrap_or(Path::new("."));
+
+    return read_lines(&source, &path.display().to_string(), directory, depth, lines);
+}
+
+/// Splits the source into lines, replacing each `:include "file"` with the
+/// lines of that file.
+fn read_lines(source: &str, file: &str, directory: &Path, depth: usize, lines: &mut Vec<SourceLine>) -> Result<(), String> {
+    for (index, text) in source.lines().enumerate() {
+        let text = strip_comment(text).trim();
+
+        if let Some(include) = text.strip_prefix(":include") {
+            let name = include.trim().trim_matches('"');
+            if depth >= MAX_INCLUDE_DEPTH {
+                return Err(format!("{}:{}: includes nested too deeply", file, index + 1));
+            }
+
+            let path: PathBuf = directory.join(name);
+            read_file(&path, depth + 1, lines).map_err(|e| format!("{}:{}: {}", file, index + 1, e))?;
+            continue;
+        }
+
+        lines.push(SourceLine {
+            file: file.to_string(),
+            number: index + 1,
+            text: text.to_string(),
+        });
+    }
+
+    return Ok(());
+}
+
+fn strip_comment(text: &str) -> &str {
+    return match text.find([';', '#']) {
+        Some(pos) => &text[..pos],
+        None => text,
+    };
+}
+
+struct Assembler {
+    address: u32,
+    labels: HashMap<String, u16>,
+    constants: HashMap<String, u16>,
+    aliases: HashMap<String, u16>,
+}
+
+impl Assembler {
+    fn new(start: u16) -> Assembler {
+        return Assembler {
+            address: start as u32,
+            labels: HashMap::new(),
+            constants: HashMap::new(),
+            aliases: HashMap::new(),
+        };
+    }
+
+    /// The first pass places labels and binds operands, the second encodes
+    /// once every label address is known.
+    fn run(mut self, lines: &[SourceLine]) -> Result<Vec<u8>, String> {
+        let mut statements = Vec::new();
+
+        for line in lines.iter() {
+            let at = |message: String| format!("{}:{}: {}", line.file, line.number, message);
+
+            if let Some(statement) = self.parse_line(&line.text).map_err(at)? {
+                statements.push((line, statement));
+            }
+        }
+
+        let mut rom = Vec::new();
+
+        for (line, statement) in statements.iter() {
+            let at = |message: String| format!("{}:{}: {}", line.file, line.number, message);
+            self.encode(statement, &mut rom).map_err(at)?;
+        }
+
+        return Ok(rom);
+    }
+
+    fn parse_line(&mut self, text: &str) -> Result<Option<Statement>, String> {
+        let mut text = text;
+
+        // Octo style `: name`, optionally followed by code.
+        if let Some(rest) = text.strip_prefix(": ") {
+            let rest = rest.trim_start();
+            let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            self.define_label(&rest[..end])?;
+            text = rest[end..].trim_start();
+        }
+
+        // Classic `name:`, optionally followed by code.
+        if let Some(first) = text.split_whitespace().next() {
+            if let Some(name) = first.strip_suffix(':') {
+                if !name.starts_with(':') {
+                    self.define_label(name)?;
+                    text = text[first.len()..].trim_start();
+                }
+            }
+        }
+
+        let words: Vec<&str> = text.split_whitespace().collect();
+        if words.is_empty() {
+            return Ok(None);
+        }
+
+        let statement = match words[0].to_lowercase().as_str() {
+            ":alias" => {
+                if words.len() != 3 {
+                    return Err(String::from("expected ':alias NAME REGISTER'"));
+                }
+                let register = self.register(words[2]).ok_or(format!("'{}' is not a register", words[2]))?;
+                self.aliases.insert(words[1].to_string(), register);
+                return Ok(None);
+            }
+            ":const" => {
+                if words.len() != 3 {
+                    return Err(String::from("expected ':const NAME VALUE'"));
+                }
+                let value = self.value(words[2])?;
+                self.constants.insert(words[1].to_string(), value);
+                return Ok(None);
+            }
+            "db" => Statement::Bytes(split_values(&words[1..])),
+            "dw" => Statement::Words(split_values(&words[1..])),
+            _ if parse_number(words[0]).is_some() => Statement::Bytes(split_values(&words)),
+            _ => self.parse_instruction(text)?,
+        };
+
+        self.address += match &statement {
+            Statement::Instruction(entry, _) => entry.size() as u32,
+            Statement::Bytes(values) => values.len() as u32,
+            Statement::Words(values) => 2 * values.len() as u32,
+        };
+        if self.address > 0x10000 {
+            return Err(String::from("program does not fit in 64 KiB of memory"));
+        }
+
+        return Ok(Some(statement));
+    }
+
+    fn define_label(&mut self, name: &str) -> Result<(), String> {
+        if name.is_empty() || parse_number(name).is_some() || self.register(name).is_some() {
+            return Err(format!("invalid label name '{}'", name));
+        }
+        if self.labels.contains_key(name) {
+            return Err(format!("label '{}' is defined twice", name));
+        }
+
+        self.labels.insert(name.to_string(), self.address as u16);
+
+        return Ok(());
+    }
+
+    /// Finds the first row of the opcode table whose syntax the text matches.
+    fn parse_instruction(&self, text: &str) -> Result<Statement, String> {
+        let (mnemonic, rest) = match text.find(char::is_whitespace) {
+            Some(pos) => (&text[..pos], text[pos..].trim()),
+            None => (text, ""),
+        };
+        let operands = split_operands(rest);
+        let mut known = false;
+
+        for entry in OPCODES.iter() {
+            let (entry_mnemonic, entry_rest) = match entry.syntax.find(' ') {
+                Some(pos) => (&entry.syntax[..pos], &entry.syntax[pos + 1..]),
+                None => (entry.syntax, ""),
+            };
+            if !entry_mnemonic.eq_ignore_ascii_case(mnemonic) {
+                continue;
+            }
+            known = true;
+
+            if let Some(fields) = self.bind(&split_operands(entry_rest), &operands) {
+                return Ok(Statement::Instruction(entry, fields));
+            }
+        }
+
+        if known {
+            return Err(format!("invalid operands for '{}': '{}'", mnemonic, rest));
+        }
+
+        return Err(format!("unknown instruction '{}'", mnemonic));
+    }
+
+    fn bind(&self, pattern: &[Vec<String>], operands: &[Vec<String>]) -> Option<Vec<(&'static str, Operand)>> {
+        if pattern.len() != operands.len() {
+            return None;
+        }
+
+        let mut fields = Vec::new();
+
+        for (expected, given) in pattern.iter().zip(operands.iter()) {
+            if expected.len() != given.len() {
+                return None;
+            }
+
+            for (token, word) in expected.iter().zip(given.iter()) {
+                if let Some(field) = PLACEHOLDERS.iter().copied().find(|field| *field == token.as_str()) {
+                    if field.starts_with('V') {
+                        fields.push((field, Operand::Register(self.register(word)?)));
+                        continue;
+                    }
+
+                    if self.register(word).is_some() || KEYWORDS.contains(&word.to_uppercase().as_str()) {
+                        return None;
+                    }
+                    fields.push((field, Operand::Value(word.clone())));
+                } else if token == "V0" {
+                    // `JP V0, nnn` names its register literally.
+                    if self.register(word) != Some(0) {
+                        return None;
+                    }
+                } else if !token.eq_ignore_ascii_case(word) {
+                    return None;
+                }
+            }
+        }
+
+        return Some(fields);
+    }
+
+    fn register(&self, word: &str) -> Option<u16> {
+        if let Some(register) = self.aliases.get(word) {
+            return Some(*register);
+        }
+
+        let digit = word.strip_prefix('V').or_else(|| word.strip_prefix('v'))?;
+        if digit.len() != 1 {
+            return None;
+        }
+
+        return u16::from_str_radix(digit, 16).ok();
+    }
+
+    fn value(&self, word: &str) -> Result<u16, String> {
+        if let Some(number) = parse_number(word) {
+            return number;
+        }
+
+        if let Some(value) = self.constants.get(word).or_else(|| self.labels.get(word)) {
+            return Ok(*value);
+        }
+
+        return Err(format!("unknown label or constant '{}'", word));
+    }
+
+    fn encode(&self, statement: &Statement, rom: &mut Vec<u8>) -> Result<(), String> {
+        match statement {
+            Statement::Instruction(entry, fields) => {
+                let mut opcode = entry.pattern;
+                let mut long = None;
+
+                for (field, operand) in fields.iter() {
+                    let value = match operand {
+                        Operand::Register(register) => *register,
+                        Operand::Value(word) => self.value(word)?,
+                    };
+                    let maximum = match *field {
+                        "nnnn" => 0xFFFF,
+                        "nnn" => 0xFFF,
+                        "nn" => 0xFF,
+                        _ => 0xF,
+                    };
+                    if value > maximum {
+                        return Err(format!("value 0x{:X} does not fit in '{}'", value, field));
+                    }
+
+                    match *field {
+                        "Vx" | "x" => opcode |= value << 8,
+                        "Vy" => opcode |= value << 4,
+                        "nnnn" => long = Some(value),
+                        _ => opcode |= value,
+                    }
+                }
+
+                rom.extend_from_slice(&opcode.to_be_bytes());
+                if let Some(long) = long {
+                    rom.extend_from_slice(&long.to_be_bytes());
+                }
+            }
+            Statement::Bytes(values) => {
+                for word in values.iter() {
+                    let value = self.value(word)?;
+                    if value > 0xFF {
+                        return Err(format!("value 0x{:X} does not fit in a byte", value));
+                    }
+                    rom.push(value as u8);
+                }
+            }
+            Statement::Words(values) => {
+                for word in values.iter() {
+                    rom.extend_from_slice(&self.value(word)?.to_be_bytes());
+                }
+            }
+        }
+
+        return Ok(());
+    }
+}
+
+/// Splits `V0, V1 - V3` into `[["V0"], ["V1", "-", "V3"]]`.
+fn split_operands(text: &str) -> Vec<Vec<String>> {
+    if text.trim().is_empty() {
+        return Vec::new();
+    }
+
+    return text
+        .split(',')
+        .map(|operand| operand.split_whitespace().map(|word| word.to_string()).collect())
+        .collect();
+}
+
+fn split_values(words: &[&str]) -> Vec<String> {
+    return words
+        .iter()
+        .flat_map(|word| word.split(','))
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+        .collect();
+}
+
+/// Reads `0x1F`, `$1F`, `0b00011111`, `%00011111` and decimal numbers,
+/// returning `None` for words that are not numbers at all.
+fn parse_number(word: &str) -> Option<Result<u16, String>> {
+    let (digits, radix) = if let Some(hex) = word.strip_prefix("0x").or_else(|| word.strip_prefix("0X")) {
+        (hex, 16)
+    } else if let Some(hex) = word.strip_prefix('$') {
+        (hex, 16)
+    } else if let Some(binary) = word.strip_prefix("0b").or_else(|| word.strip_prefix('%')) {
+        (binary, 2)
+    } else if word.starts_with(|c: char| c.is_ascii_digit()) {
+        (word, 10)
+    } else {
+        return None;
+    };
+
+    return Some(u16::from_str_radix(digits, radix).map_err(|_| format!("invalid number '{}'", word)));
+}
@@ -0,0 +1,297 @@
+use crate::cpu::Cpu;
+use crate::framebuffer::{Framebuffer, HIRES_COLUMNS, HIRES_ROWS};
+use crate::frontend::Audio;
+use crate::image;
+use crate::palette::{Palette, COLORS};
+use crate::speaker::WavSpeaker;
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+/// Image pixels per CHIP-8 pixel of screenshots and recordings by default.
+pub const DEFAULT_SCALE: usize = 4;
+
+/// The largest scale whose frames still fit the 16-bit sizes of GIF images.
+pub const MAX_SCALE: usize = u16::MAX as usize / HIRES_COLUMNS as usize;
+
+/// How screenshots and recordings look.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Settings {
+    pub palette: Palette,
+    pub scale: usize,
+    /// Whether recordings also write the beeper into a WAV file next to them.
+    pub audio: bool,
+}
+
+impl Settings {
+    pub fn new() -> Settings {
+        return Settings {
+            palette: Palette::new(),
+            scale: DEFAULT_SCALE,
+            audio: false,
+        };
+    }
+}
+
+impl Default for Settings {
+    fn default() -> Settings {
+        return Settings::new();
+    }
+}
+
+/// The first of `roms/BRIX-1.png`, `roms/BRIX-2.png` and so on that does not
+/// exist yet.
+pub fn next_path(rom: &Path, extension: &str) -> PathBuf {
+    let stem = rom.file_stem().and_then(|stem| stem.to_str()).filter(|stem| *stem != "-").unwrap_or("capture");
+
+    return (1..)
+        .map(|number| rom.with_file_name(format!("{}-{}.{}", stem, number, extension)))
+        .find(|path| !path.exists())
+        .unwrap();
+}
+
+pub fn save_screenshot(framebuffer: &Framebuffer, settings: &Settings, path: &Path) -> Result<(), String> {
+    let png = image::png(framebuffer, &settings.palette, settings.scale);
+
+    return std::fs::write(path, png).map_err(|e| format!("Could not write '{}': {}", path.display(), e));
+}
+
+enum Output {
+    Gif(Gif),
+    /// Headerless 8-bit RGB frames, one per 60 Hz frame.
+    Raw(BufWriter<File>),
+}
+
+/// Records every frame a ROM runs, as an animated GIF when the path ends in
+/// `.gif` and as raw RGB video otherwise. Frames are always the size of the
+/// hi-res screen, so lo-res pixels are twice as large.
+pub struct Recorder {
+    pub path: PathBuf,
+    pub frames: u32,
+    settings: Settings,
+    output: Output,
+    audio: Option<WavSpeaker>,
+}
+
+impl Recorder {
+    pub fn create(path: &Path, settings: &Settings) -> Result<Recorder, String> {
+        let file = File::create(path).map_err(|e| format!("Could not create '{}': {}", path.display(), e))?;
+        let is_gif = path.extension().map(|extension| extension.eq_ignore_ascii_case("gif")).unwrap_or(false);
+        let output = if is_gif {
+            let gif = Gif::new(BufWriter::new(file), settings);
+            Output::Gif(gif.map_err(|e| format!("Could not write '{}': {}", path.display(), e))?)
+        } else {
+            Output::Raw(BufWriter::new(file))
+        };
+        let audio = match settings.audio {
+            true => Some(WavSpeaker::new(&path.with_extension("wav"))?),
+            false => None,
+        };
+
+        return Ok(Recorder {
+            path: path.to_path_buf(),
+            frames: 0,
+            settings: settings.clone(),
+            output,
+            audio,
+        });
+    }
+
+    /// The width and height of the recorded frames.
+    pub fn size(&self) -> (usize, usize) {
+        return (HIRES_COLUMNS as usize * self.settings.scale, HIRES_ROWS as usize * self.settings.scale);
+    }
+
+    /// Adds the screen and sound of the frame `cpu` just ran.
+    pub fn record(&mut self, cpu: &Cpu) -> Result<(), String> {
+        let scale = self.settings.scale * (HIRES_COLUMNS / cpu.framebuffer.width) as usize;
+        let written = match &mut self.output {
+            Output::Gif(gif) => gif.add_frame(image::scaled(&cpu.framebuffer, scale)),
+            Output::Raw(writer) => writer.write_all(&image::rgb(&cpu.framebuffer, &self.settings.palette, scale)),
+        };
+        written.map_err(|e| format!("Could not write '{}': {}", self.path.display(), e))?;
+
+        if let Some(audio) = &mut self.audio {
+            if cpu.quirks.xo_chip {
+                audio.set_pattern(&cpu.audio_pattern, cpu.pitch);
+            }
+            audio.set_beeping(cpu.sound_timer > 0);
+        }
+        self.frames += 1;
+
+        return Ok(());
+    }
+
+    /// Completes the file. The WAV file is completed when the recorder is
+    /// dropped.
+    pub fn finish(self) -> Result<(), String> {
+        let path = self.path;
+        let finished = match self.output {
+            Output::Gif(gif) => gif.finish(),
+            Output::Raw(mut writer) => writer.flush(),
+        };
+
+        return finished.map_err(|e| format!("Could not write '{}': {}", path.display(), e));
+    }
+}
+
+/// Writes an animated GIF. Browsers slow down frames shorter than 2/100 s,
+/// so frames are merged until they last that long, dropping the ones in
+/// between; runs of identical frames become a single longer one.
+struct Gif {
+    writer: BufWriter<File>,
+    width: usize,
+    height: usize,
+    /// The frame waiting for its length to be known, and the 60 Hz frame it
+    /// started at.
+    pending: Option<(Vec<u32>, u32)>,
+    ticks: u32,
+}
+
+impl Gif {
+    fn new(writer: BufWriter<File>, settings: &Settings) -> std::io::Result<Gif> {
+        let mut gif = Gif {
+            writer,
+            width: HIRES_COLUMNS as usize * settings.scale,
+            height: HIRES_ROWS as usize * settings.scale,
+            pending: None,
+            ticks: 0,
+        };
+
+        let mut header = b"GIF89a".to_vec();
+        header.extend_from_slice(&(gif.width as u16).to_le_bytes());
+        header.extend_from_slice(&(gif.height as u16).to_le_bytes());
+        // A global color table of 2^(3 + 1) = 16 colors.
+        header.extend_from_slice(&[0xF3, 0, 0]);
+        for color in settings.palette.colors.iter() {
+            header.extend_from_slice(&[(color >> 16) as u8, (color >> 8) as u8, *color as u8]);
+        }
+        // Loop forever.
+        header.extend_from_slice(b"\x21\xFF\x0BNETSCAPE2.0\x03\x01\x00\x00\x00");
+
+        gif.writer.write_all(&header)?;
+
+        return Ok(gif);
+    }
+
+    fn add_frame(&mut self, pixels: Vec<u32>) -> std::io::Result<()> {
+        let ticks = self.ticks;
+        self.ticks += 1;
+
+        match self.pending.take() {
+            Some((pending, start)) if pending == pixels => self.pending = Some((pending, start)),
+            Some((pending, start)) if centiseconds(ticks) - centiseconds(start) >= 2 => {
+                self.write_frame(&pending, centiseconds(ticks) - centiseconds(start))?;
+                self.pending = Some((pixels, ticks));
+            }
+            Some((_, start)) => self.pending = Some((pixels, start)),
+            None => self.pending = Some((pixels, ticks)),
+        }
+
+        return Ok(());
+    }
+
+    fn write_frame(&mut self, pixels: &[u32], delay: u32) -> std::io::Result<()> {
+        let mut out = Vec::new();
+
+        // Graphic control extension: no disposal, no transparency.
+        out.extend_from_slice(&[0x21, 0xF9, 4, 0]);
+        out.extend_from_slice(&(delay as u16).to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+
+        // Image descriptor covering the whole screen.
+        out.extend_from_slice(&[0x2C, 0, 0, 0, 0]);
+        out.extend_from_slice(&(self.width as u16).to_le_bytes());
+        out.extend_from_slice(&(self.height as u16).to_le_bytes());
+        out.push(0);
+
+        let indexes: Vec<u8> = pixels.iter().map(|pixel| (*pixel as usize % COLORS) as u8).collect();
+        out.push(4);
+        for block in lzw(4, &indexes).chunks(255) {
+            out.push(block.len() as u8);
+            out.extend_from_slice(block);
+        }
+        out.push(0);
+
+        return self.writer.write_all(&out);
+    }
+
+    fn finish(mut self) -> std::io::Result<()> {
+        if let Some((pending, start)) = self.pending.take() {
+            let delay = centiseconds(self.ticks) - centiseconds(start);
+            self.write_frame(&pending, delay.max(2))?;
+        }
+        self.writer.write_all(&[0x3B])?;
+
+        return self.writer.flush();
+    }
+}
+
+/// When the 60 Hz frame `tick` starts, in the hundredths of a second GIF
+/// delays are counted in.
+fn centiseconds(tick: u32) -> u32 {
+    return (tick * 100 + 30) / 60;
+}
+
+/// Compresses palette indexes with the variable width LZW of GIF images.
+fn lzw(minimum_size: u32, indexes: &[u8]) -> Vec<u8> {
+    let clear = 1u32 << minimum_size;
+    let end = clear + 1;
+    let mut width = minimum_size + 1;
+    let mut next = end + 1;
+    let mut table: HashMap<(u32, u8), u32> = HashMap::new();
+
+    let mut out = Vec::new();
+    let mut bits = 0u32;
+    let mut count = 0;
+    let mut emit = |code: u32, width: u32| {
+        bits |= code << count;
+        count += width;
+        while count >= 8 {
+            out.push(bits as u8);
+            bits >>= 8;
+            count -= 8;
+        }
+    };
+
+    emit(clear, width);
+    let mut prefix: Option<u32> = None;
+    for index in indexes.iter() {
+        let code = match prefix {
+            Some(code) => code,
+            None => {
+                prefix = Some(*index as u32);
+                continue;
+            }
+        };
+        if let Some(longer) = table.get(&(code, *index)) {
+            prefix = Some(*longer);
+            continue;
+        }
+
+        emit(code, width);
+        if next < 4096 {
+            if next == 1 << width {
+                width += 1;
+            }
+            table.insert((code, *index), next);
+            next += 1;
+        } else {
+            emit(clear, width);
+            table.clear();
+            width = minimum_size + 1;
+            next = end + 1;
+        }
+        prefix = Some(*index as u32);
+    }
+    if let Some(code) = prefix {
+        emit(code, width);
+    }
+    emit(end, width);
+    if count > 0 {
+        out.push(bits as u8);
+    }
+
+    return out;
+}
@@ -1,34 +1,510 @@
-use crate::cpu::Cpu;
-use std::{thread, time};
+use crate::capture::{self, Recorder, Settings};
+use crate::cpu::{Cpu, BIG_FONT_ADDRESS};
+use crate::debugger::{DebugCommand, Debugger, HELP};
+use crate::error::Chip8Error;
+use crate::framebuffer::{Framebuffer, COLUMNS, ROWS};
+use crate::frontend::{Audio, Command, Display, Input};
+use crate::keyboard::Keyboard;
+use crate::movie::{Frame, Movie};
+use crate::rewind::{Rewind, DEFAULT_BUDGET_MB};
+use crate::rom;
+use crate::scheduler::{ClockRate, Scheduler};
+use crate::savestate;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::Receiver;
+use std::thread;
+use std::time::Instant;
+
 pub struct Chip8 {
     pub cpu: Cpu,
+    pub scheduler: Scheduler,
+    pub debugger: Debugger,
+    /// Debugger commands typed on stdin, when the console is enabled.
+    pub console: Option<Receiver<String>>,
+    pub rewind: Rewind,
+    /// While set, every tick restores an earlier frame instead of running.
+    rewinding: bool,
+    /// The keypad of every frame run so far, while recording a movie.
+    pub recording: Option<Movie>,
+    /// A movie replacing the host keypad, and the next frame to replay.
+    playback: Option<(Movie, usize)>,
+    /// The keypad key the remap screen is asking for; the game is frozen
+    /// meanwhile.
+    remapping: Option<u8>,
+    /// How screenshots and recordings look.
+    pub capture: Settings,
+    /// Records every frame run, while capturing video.
+    pub recorder: Option<Recorder>,
+    rom: PathBuf,
+    /// The unpacked contents of `rom`, for loading it again.
+    program: Vec<u8>,
+    display: Box<dyn Display>,
+    input: Box<dyn Input>,
+    audio: Box<dyn Audio>,
 }
 
 impl Chip8 {
-    pub fn new(mut cpu: Cpu) -> Chip8 {
+    pub fn new(
+        mut cpu: Cpu,
+        rom: &Path,
+        display: Box<dyn Display>,
+        input: Box<dyn Input>,
+        audio: Box<dyn Audio>,
+    ) -> Result<Chip8, String> {
         cpu.load_sprites();
-        cpu.load_program();
+        let program = rom::read(rom)?;
+        cpu.load_rom(&program)?;
+
+        let scheduler = Scheduler::new(ClockRate::PerFrame(cpu.speed as u32));
 
-        return Chip8 { cpu };
+        return Ok(Chip8 {
+            cpu,
+            scheduler,
+            debugger: Debugger::new(),
+            console: None,
+            rewind: Rewind::new(DEFAULT_BUDGET_MB << 20),
+            rewinding: false,
+            recording: None,
+            playback: None,
+            capture: Settings::new(),
+            recorder: None,
+            remapping: None,
+            rom: rom.to_path_buf(),
+            program,
+            display,
+            input,
+            audio,
+        });
     }
 
     pub fn run(&mut self) -> Result<(), String> {
-        let ten_millis = time::Duration::from_millis(100);
+        self.scheduler.start(Instant::now());
+        self.rewind.record(savestate::save(&self.cpu), 0);
 
         'running: loop {
-            if self.cpu.delay_timer > 0 {
-                self.cpu.delay_timer -= 1;
+            // A movie being played owns the keypad, so host keys go nowhere.
+            let mut ignored = Keyboard::new();
+            let keyboard = match self.playback {
+                Some(_) => &mut ignored,
+                None => &mut self.cpu.keyboard,
+            };
+            for command in self.input.poll(keyboard) {
+                match command {
+                    Command::Quit => break 'running,
+                    Command::TogglePause => self.set_paused(!self.cpu.paused),
+                    Command::StepInstruction => self.step_instruction(),
+                    Command::StepFrame => self.step_frame(),
+                    Command::SaveState(slot) => match self.save_state(slot) {
+                        Ok(path) => println!("Saved state to {}", path.display()),
+                        Err(message) => eprintln!("{}", message),
+                    },
+                    Command::LoadState(slot) => match self.load_state(slot) {
+                        Ok(path) => println!("Loaded state from {}", path.display()),
+                        Err(message) => eprintln!("{}", message),
+                    },
+                    Command::Rewind(rewinding) => self.rewinding = rewinding,
+                    Command::Remap(key) => {
+                        self.remapping = key;
+                        self.cpu.framebuffer.dirty = true;
+                    }
+                    Command::ToggleFullscreen => {
+                        if let Err(message) = self.display.toggle_fullscreen() {
+                            eprintln!("Could not toggle fullscreen: {}", message);
+                        }
+                    }
+                    Command::CycleFilter => {
+                        if let Some(name) = self.display.cycle_filter() {
+                            println!("Display filter: {}", name);
+                        }
+                        self.cpu.framebuffer.dirty = true;
+                    }
+                    Command::Redraw => self.cpu.framebuffer.dirty = true,
+                    Command::Screenshot => match self.screenshot() {
+                        Ok(path) => println!("Saved screenshot to {}", path.display()),
+                        Err(message) => eprintln!("{}", message),
+                    },
+                    Command::ToggleCapture => {
+                        let toggled = match self.recorder.is_some() {
+                            true => self.stop_capture(),
+                            false => self.start_capture(&capture::next_path(&self.rom, "gif")),
+                        };
+                        if let Err(message) = toggled {
+                            eprintln!("{}", message);
+                        }
+                    }
+                }
+            }
+
+            self.read_console();
+
+            let ticks = self.scheduler.due_ticks(Instant::now());
+
+            for _ in 0..ticks {
+                if self.remapping.is_some() {
+                    break;
+                }
+                if let Err(error) = self.tick() {
+                    // Keep the window open on the failing state instead of
+                    // quitting, so it can be inspected.
+                    self.break_into(&error.to_string());
+                }
             }
 
-            let result = self.cpu.cycle();
-            
-            if result > 0 {
+            if self.cpu.exited {
                 break 'running;
             }
 
-            thread::sleep(ten_millis);
+            // Frames are only presented once per loop, so when several ticks
+            // had to be caught up the intermediate frames are skipped.
+            if self.cpu.framebuffer.dirty || self.display.is_animating() {
+                match self.remapping {
+                    Some(key) => self.display.render(&self.remap_screen(key))?,
+                    None => self.display.render(&self.cpu.framebuffer)?,
+                }
+                self.cpu.framebuffer.dirty = false;
+            }
+
+            thread::sleep(self.scheduler.time_until_next_tick(Instant::now()));
         }
 
         Ok(())
     }
+
+    /// Runs one 60 Hz timer tick worth of instructions.
+    pub fn tick(&mut self) -> Result<(), Chip8Error> {
+        if self.rewinding {
+            if self.frame_back() {
+                self.movie_back();
+            }
+        } else if !self.cpu.paused {
+            self.advance()?;
+
+            if let Some(recorder) = &mut self.recorder {
+                if let Err(message) = recorder.record(&self.cpu) {
+                    eprintln!("{}, stopping the capture", message);
+                    self.recorder = None;
+                }
+            }
+        }
+
+        if self.cpu.quirks.xo_chip {
+            self.audio.set_pattern(&self.cpu.audio_pattern, self.cpu.pitch);
+        }
+        self.audio.set_beeping(!self.cpu.paused && self.cpu.sound_timer > 0);
+
+        return Ok(());
+    }
+
+    /// The big font digit of the keypad key to press next, in the middle of
+    /// an otherwise empty screen.
+    fn remap_screen(&self, key: u8) -> Framebuffer {
+        let mut screen = Framebuffer::new();
+        let glyph = BIG_FONT_ADDRESS + key as usize * 10;
+
+        for (row, byte) in self.cpu.memory[glyph..glyph + 10].iter().enumerate() {
+            for column in 0..8 {
+                if byte & (0x80 >> column) != 0 {
+                    screen.set_pixel((COLUMNS - 8) / 2 + column, (ROWS - 10) / 2 + row as isize, 1);
+                }
+            }
+        }
+
+        return screen;
+    }
+
+    /// Saves the screen as the next free `<ROM>-N.png` next to the ROM.
+    pub fn screenshot(&self) -> Result<PathBuf, String> {
+        let path = capture::next_path(&self.rom, "png");
+        capture::save_screenshot(&self.cpu.framebuffer, &self.capture, &path)?;
+
+        return Ok(path);
+    }
+
+    /// Starts recording every frame into `path`.
+    pub fn start_capture(&mut self, path: &Path) -> Result<(), String> {
+        self.stop_capture()?;
+        self.recorder = Some(Recorder::create(path, &self.capture)?);
+        println!("Capturing to {}", path.display());
+
+        return Ok(());
+    }
+
+    pub fn stop_capture(&mut self) -> Result<(), String> {
+        if let Some(recorder) = self.recorder.take() {
+            let (path, frames) = (recorder.path.clone(), recorder.frames);
+            recorder.finish()?;
+            println!("Captured {} frames to {}", frames, path.display());
+        }
+
+        return Ok(());
+    }
+
+    /// Starts recording a movie. Only meaningful right after loading the ROM.
+    pub fn record_movie(&mut self) {
+        self.recording = Some(Movie::new(&self.cpu, self.scheduler.rate));
+    }
+
+    /// Replays a movie from power-on, feeding its keypad instead of the host's
+    /// until it runs out of frames.
+    pub fn play_movie(&mut self, movie: Movie) -> Result<(), String> {
+        self.scheduler.rate = movie.start(&mut self.cpu, &self.program)?;
+        self.playback = Some((movie, 0));
+
+        return Ok(());
+    }
+
+    /// Runs a frame with the keypad of the movie being played or recorded.
+    fn advance(&mut self) -> Result<u32, Chip8Error> {
+        let mut finished = false;
+        if let Some((movie, position)) = &mut self.playback {
+            match movie.frames.get(*position) {
+                Some(frame) => frame.apply(&mut self.cpu.keyboard),
+                None => finished = true,
+            }
+            *position += 1;
+        }
+        if finished {
+            println!("Movie finished, continuing with live input");
+            self.playback = None;
+        }
+
+        if let Some(movie) = &mut self.recording {
+            movie.frames.push(Frame::from_keyboard(&self.cpu.keyboard));
+        }
+
+        let executed = self.run_frame()?;
+        self.rewind.record(savestate::save(&self.cpu), executed);
+
+        return Ok(executed);
+    }
+
+    /// Keeps a movie in step with a frame undone by rewinding.
+    fn movie_back(&mut self) {
+        if let Some(movie) = &mut self.recording {
+            movie.frames.pop();
+        }
+        if let Some((_, position)) = &mut self.playback {
+            *position = position.saturating_sub(1);
+        }
+    }
+
+    /// Runs a frame and returns how many instructions it executed.
+    fn run_frame(&mut self) -> Result<u32, Chip8Error> {
+        let mut executed = 0;
+
+        for _ in 0..self.scheduler.instructions_for_tick() {
+            if self.cpu.exited {
+                break;
+            }
+
+            if let Some(reason) = self.debugger.check_before(&self.cpu) {
+                self.break_into(&reason);
+                break;
+            }
+
+            let hit = self.execute()?;
+            executed += 1;
+            if hit {
+                break;
+            }
+        }
+
+        self.cpu.update_timers();
+
+        return Ok(executed);
+    }
+
+    /// Executes the instruction at `pc`, returning true when it hit a watchpoint.
+    fn execute(&mut self) -> Result<bool, Chip8Error> {
+        let address = self.cpu.pc;
+
+        if self.debugger.trace {
+            if let Ok(opcode) = self.cpu.read_word(address) {
+                let name = self.cpu.get_type(opcode).unwrap_or("Unknown");
+                println!("{:03X}: {:04X} {}", address, opcode, name);
+            }
+        }
+
+        self.cpu.step()?;
+
+        if let Some(reason) = self.debugger.check_after(&self.cpu, address) {
+            self.break_into(&reason);
+            return Ok(true);
+        }
+
+        return Ok(false);
+    }
+
+    /// Writes quick-save slot `slot` next to the ROM.
+    pub fn save_state(&self, slot: u8) -> Result<PathBuf, String> {
+        let path = savestate::slot_path(&self.rom, slot);
+        savestate::save_file(&self.cpu, &path)?;
+
+        return Ok(path);
+    }
+
+    pub fn load_state(&mut self, slot: u8) -> Result<PathBuf, String> {
+        let path = savestate::slot_path(&self.rom, slot);
+        savestate::load_file(&mut self.cpu, &path)?;
+        self.debugger.resume(&self.cpu);
+
+        return Ok(path);
+    }
+
+    pub fn set_paused(&mut self, paused: bool) {
+        if self.cpu.paused && !paused {
+            self.debugger.resume(&self.cpu);
+        }
+        self.cpu.paused = paused;
+    }
+
+    /// Pauses and shows why, together with the machine state.
+    pub fn break_into(&mut self, reason: &str) {
+        self.cpu.paused = true;
+        println!("{}\n{}", reason, Debugger::registers(&self.cpu));
+    }
+
+    /// Pauses, if needed, and executes exactly one instruction.
+    pub fn step_instruction(&mut self) {
+        self.cpu.paused = true;
+
+        match self.execute() {
+            Ok(hit) => {
+                self.rewind.record(savestate::save(&self.cpu), 1);
+                if !hit {
+                    println!("{}", Debugger::registers(&self.cpu));
+                }
+            }
+            Err(error) => self.break_into(&error.to_string()),
+        }
+    }
+
+    /// Pauses, if needed, and executes one frame including its timer tick.
+    pub fn step_frame(&mut self) {
+        self.cpu.paused = true;
+        self.debugger.resume(&self.cpu);
+
+        if let Err(error) = self.advance() {
+            return self.break_into(&error.to_string());
+        }
+        println!("{}", Debugger::registers(&self.cpu));
+    }
+
+    /// Restores the previous recorded frame, or the state before the last
+    /// debugger step. Returns false when there is nothing left to rewind.
+    pub fn frame_back(&mut self) -> bool {
+        let state = match self.rewind.back() {
+            Some((state, _)) => state,
+            None => return false,
+        };
+
+        if let Err(message) = savestate::load(&mut self.cpu, &state) {
+            eprintln!("{}", message);
+            return false;
+        }
+        self.debugger.resume(&self.cpu);
+
+        return true;
+    }
+
+    /// Goes back exactly one instruction, by restoring the snapshot before it
+    /// and running forward again to the instruction before the current one.
+    pub fn step_back(&mut self) -> bool {
+        self.cpu.paused = true;
+
+        let (state, instructions) = match self.rewind.back() {
+            Some(step) => step,
+            None => return false,
+        };
+        if let Err(message) = savestate::load(&mut self.cpu, &state) {
+            eprintln!("{}", message);
+            return false;
+        }
+
+        if instructions > 1 {
+            for _ in 1..instructions {
+                if let Err(error) = self.cpu.step() {
+                    self.break_into(&error.to_string());
+                    break;
+                }
+            }
+            self.rewind.record(savestate::save(&self.cpu), instructions - 1);
+        }
+        self.debugger.resume(&self.cpu);
+
+        return true;
+    }
+
+    fn read_console(&mut self) {
+        let lines: Vec<String> = match &self.console {
+            Some(console) => console.try_iter().collect(),
+            None => return,
+        };
+
+        for line in lines {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match Debugger::parse(&line) {
+                Ok(command) => self.debug_command(command),
+                Err(message) => println!("{}", message),
+            }
+        }
+    }
+
+    pub fn debug_command(&mut self, command: DebugCommand) {
+        match command {
+            DebugCommand::Continue => self.set_paused(false),
+            DebugCommand::Pause => self.break_into("Paused"),
+            DebugCommand::Step(count) => {
+                for _ in 0..count {
+                    self.cpu.paused = true;
+                    match self.execute() {
+                        Ok(hit) => {
+                            self.rewind.record(savestate::save(&self.cpu), 1);
+                            if hit {
+                                return;
+                            }
+                        }
+                        Err(error) => return self.break_into(&error.to_string()),
+                    }
+                }
+                println!("{}", Debugger::registers(&self.cpu));
+            }
+            DebugCommand::Frame => self.step_frame(),
+            DebugCommand::StepBack(count) => {
+                for _ in 0..count {
+                    if !self.step_back() {
+                        println!("Nothing left to rewind");
+                        break;
+                    }
+                }
+                println!("{}", Debugger::registers(&self.cpu));
+            }
+            DebugCommand::FrameBack => {
+                self.cpu.paused = true;
+                if !self.frame_back() {
+                    println!("Nothing left to rewind");
+                }
+                println!("{}", Debugger::registers(&self.cpu));
+            }
+            DebugCommand::Add(breakpoint) => {
+                self.debugger.add(breakpoint);
+                println!("{}", self.debugger.list());
+            }
+            DebugCommand::List => println!("{}", self.debugger.list()),
+            DebugCommand::Delete(index) => match self.debugger.delete(index) {
+                Ok(()) => println!("{}", self.debugger.list()),
+                Err(message) => println!("{}", message),
+            },
+            DebugCommand::Registers => println!("{}", Debugger::registers(&self.cpu)),
+            DebugCommand::Memory(start, length) => println!("{}", Debugger::memory(&self.cpu, start, length)),
+            DebugCommand::Trace => {
+                self.debugger.trace = !self.debugger.trace;
+                println!("Trace {}", if self.debugger.trace { "on" } else { "off" });
+            }
+            DebugCommand::Help => println!("{}", HELP),
+        }
+    }
 }
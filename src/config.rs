@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// Settings read from INI-style files:
+///
+/// ```ini
+/// [quirks]
+/// profile = schip
+/// ```
+///
+/// The global file is loaded first and a file next to the ROM, with the same
+/// name and an `.ini` extension, overrides it key by key.
+#[derive(Clone)]
+pub struct Config {
+    sections: HashMap<String, HashMap<String, String>>,
+}
+
+impl Config {
+    pub fn new() -> Config {
+        return Config {
+            sections: HashMap::new(),
+        };
+    }
+
+    /// `$XDG_CONFIG_HOME/rust-chip/config.ini`, falling back to `~/.config`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = match env::var_os("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => PathBuf::from(env::var_os("HOME")?).join(".config"),
+        };
+
+        return Some(base.join("rust-chip").join("config.ini"));
+    }
+
+    /// The per-ROM override file, e.g. `roms/BRIX.ini` for `roms/BRIX.ch8`.
+    pub fn rom_path(rom: &Path) -> PathBuf {
+        return rom.with_extension("ini");
+    }
+
+    /// Loads a config file, treating a missing file as an empty config.
+    pub fn load(path: &Path) -> Result<Config, String> {
+        match fs::read_to_string(path) {
+            Ok(text) => Config::parse(&text).map_err(|e| format!("{}: {}", path.display(), e)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(Config::new()),
+            Err(e) => Err(format!("Could not read config '{}': {}", path.display(), e)),
+        }
+    }
+
+    pub fn parse(text: &str) -> Result<Config, String> {
+        let mut config = Config::new();
+        let mut section = String::new();
+
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                section = line[1..line.len() - 1].trim().to_lowercase();
+                continue;
+            }
+
+            match line.find('=') {
+                Some(pos) => {
+                    let key = line[..pos].trim().to_lowercase();
+                    let value = line[pos + 1..].trim().to_string();
+                    config.set(&section, &key, &value);
+                }
+                None => return Err(format!("line {}: expected 'key = value'", number + 1)),
+            }
+        }
+
+        return Ok(config);
+    }
+
+    pub fn get(&self, section: &str, key: &str) -> Option<&str> {
+        return self.sections.get(section)?.get(key).map(|value| value.as_str());
+    }
+
+    pub fn set(&mut self, section: &str, key: &str, value: &str) {
+        self.sections
+            .entry(section.to_string())
+            .or_default()
+            .insert(key.to_string(), value.to_string());
+    }
+
+    /// All `key = value` pairs of a section, in no particular order.
+    pub fn section(&self, section: &str) -> Vec<(&str, &str)> {
+        return match self.sections.get(section) {
+            Some(entries) => entries.iter().map(|(k, v)| (k.as_str(), v.as_str())).collect(),
+            None => Vec::new(),
+        };
+    }
+
+    /// Replaces one section of a config file with `entries`, keeping the rest
+    /// of the file, comments included, as it is.
+    pub fn write_section(path: &Path, section: &str, entries: &[(String, String)]) -> Result<(), String> {
+        let text = match fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(format!("Could not read config '{}': {}", path.display(), e)),
+        };
+
+        let mut out = String::new();
+        let mut skipping = false;
+        for line in text.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') && trimmed.ends_with(']') {
+                skipping = trimmed[1..trimmed.len() - 1].trim().eq_ignore_ascii_case(section);
+            }
+            if !skipping {
+                out.push_str(line);
+                out.push('\n');
+            }
+        }
+
+        if !out.is_empty() && !out.ends_with("\n\n") {
+            out.push('\n');
+        }
+        out.push_str(&format!("[{}]\n", section));
+        for (key, value) in entries.iter() {
+            out.push_str(&format!("{} = {}\n", key, value));
+        }
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("Could not create '{}': {}", dir.display(), e))?;
+        }
+
+        return fs::write(path, out).map_err(|e| format!("Could not write config '{}': {}", path.display(), e));
+    }
+
+    /// Copies every key of `other` over this config.
+    pub fn merge(&mut self, other: Config) {
+        for (section, entries) in other.sections {
+            for (key, value) in entries {
+                self.set(&section, &key, &value);
+            }
+        }
+    }
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        return Config::new();
+    }
+}
+
+pub fn parse_bool(key: &str, value: &str) -> Result<bool, String> {
+    match value.to_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(format!("Invalid value '{}' for '{}', expected true or false", value, key)),
+    }
+}
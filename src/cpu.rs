@@ -1,41 +1,121 @@
-use crate::renderer::Renderer;
-use sdl2::event::Event;
-use sdl2::keyboard::Keycode;
-use std::{borrow::BorrowMut, fs::File};
-use std::io;
-use std::io::prelude::*;
+use crate::error::Chip8Error;
+use crate::framebuffer::{Framebuffer, PLANES};
+use crate::keyboard::Keyboard;
+use crate::opcodes::{self, Op};
+use crate::quirks::Quirks;
+use crate::rng::Rng;
+use crate::{rom, savestate};
+use std::ops::Range;
+use std::path::Path;
+
+pub const PROGRAM_START: usize = 0x200;
+pub const MEMORY_SIZE: usize = 0x1000;
+pub const XO_CHIP_MEMORY_SIZE: usize = 0x10000;
+pub const FONT_ADDRESS: usize = 0x000;
+pub const BIG_FONT_ADDRESS: usize = 0x050;
+pub const RPL_FLAGS: usize = 16;
+pub const STACK_DEPTH: usize = 16;
+
+/// Progress of `FX0A`, which executes again and again until it has a key.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum KeyWait {
+    Idle,
+    /// Waiting for a key to go down. Keys in `held` were already down when
+    /// the wait began and only count once released and pressed again.
+    Waiting { held: u16 },
+    /// The key went down; with the `key_wait_release` quirk the wait goes on
+    /// until it is released.
+    Pressed(u8),
+}
 
 pub struct Cpu {
-    pub renderer: Renderer,
+    pub framebuffer: Framebuffer,
+    pub keyboard: Keyboard,
     pub memory: Vec<u8>,
     pub delay_timer: u8,
+    pub sound_timer: u8,
     pub v: Vec<u8>,
     pub i: u16,
     pub pc: u16,
+    /// Return addresses, one slot per nesting level; `sp` is the number in use.
     pub stack: Vec<u16>,
+    pub sp: usize,
     pub speed: u8,
     pub paused: bool,
+    pub quirks: Quirks,
+    /// Set by every 60 Hz tick; lets `DXYN` honour the display wait quirk.
+    pub vblank: bool,
+    /// SUPER-CHIP RPL user flags, written by `FX75` and read by `FX85`.
+    pub rpl: Vec<u8>,
+    /// Set by `00FD`; the frontend should stop running the ROM.
+    pub exited: bool,
+    /// XO-CHIP 1-bit audio samples loaded by `F002`.
+    pub audio_pattern: Vec<u8>,
+    /// XO-CHIP playback pitch set by `FX3A`; 64 plays the pattern at 4000 Hz.
+    pub pitch: u8,
+    /// Memory written by the last instruction, for debugger watchpoints.
+    pub last_write: Option<Range<usize>>,
+    /// Identifies the loaded ROM, so save states are only restored into it.
+    pub rom_hash: u64,
+    /// Source of `CXNN`, seeded randomly unless a run has to be reproduced.
+    pub rng: Rng,
+    /// Where `load_program` puts the ROM and starts executing it.
+    pub load_address: u16,
+    pub key_wait: KeyWait,
+}
+
+impl Default for Cpu {
+    fn default() -> Cpu {
+        return Cpu::new();
+    }
 }
 
 impl Cpu {
-    pub fn new(renderer: Renderer) -> Cpu {
-        let memory = vec![0; 4096];
+    pub fn new() -> Cpu {
+        let memory = vec![0; MEMORY_SIZE];
         let v = vec![0; 16];
-        let stack = Vec::new();
+        let stack = vec![0; STACK_DEPTH];
 
         return Cpu {
-            renderer,
+            framebuffer: Framebuffer::new(),
+            keyboard: Keyboard::new(),
             memory,
             delay_timer: 60,
+            sound_timer: 0,
             v,
             i: 0x00,
             pc: 0x200,
             stack,
+            sp: 0,
             speed: 1,
             paused: false,
+            quirks: Quirks::default(),
+            vblank: true,
+            rpl: vec![0; RPL_FLAGS],
+            exited: false,
+            // A 500 Hz square wave until the ROM loads its own pattern.
+            audio_pattern: vec![0xF0; 16],
+            pitch: 64,
+            last_write: None,
+            rom_hash: 0,
+            rng: Rng::new(rand::random()),
+            load_address: PROGRAM_START as u16,
+            key_wait: KeyWait::Idle,
         };
     }
 
+    /// Changes how many calls can be nested, e.g. 12 on the COSMAC VIP.
+    /// Empties the stack.
+    pub fn set_stack_depth(&mut self, depth: usize) {
+        self.stack = vec![0; depth];
+        self.sp = 0;
+    }
+
+    /// The return addresses currently on the stack, oldest first.
+    pub fn call_stack(&self) -> &[u16] {
+        return &self.stack[..self.sp];
+    }
+
     pub fn load_sprites(&mut self) {
         let sprites = [
             0xF0, 0x90, 0x90, 0x90, 0xF0, /* 0 */
@@ -56,441 +136,617 @@ impl Cpu {
             0xF0, 0x80, 0xF0, 0x80, 0x80,
         ]; // f
 
-        let mut i = 0;
+        // SUPER-CHIP 8x10 digits, used by FX30
+        let big_sprites = [
+            0xFF, 0xFF, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, /* 0 */
+            0x18, 0x78, 0x78, 0x18, 0x18, 0x18, 0x18, 0x18, 0xFF, 0xFF, /* 1 */
+            0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, /* 2 */
+            0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, /* 3 */
+            0xC3, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0x03, 0x03, /* 4 */
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, /* 5 */
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, /* 6 */
+            0xFF, 0xFF, 0x03, 0x03, 0x06, 0x0C, 0x18, 0x18, 0x18, 0x18, /* 7 */
+            0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, /* 8 */
+            0xFF, 0xFF, 0xC3, 0xC3, 0xFF, 0xFF, 0x03, 0x03, 0xFF, 0xFF, /* 9 */
+            0x7E, 0xFF, 0xC3, 0xC3, 0xC3, 0xFF, 0xFF, 0xC3, 0xC3, 0xC3, /* a */
+            0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, 0xC3, 0xC3, 0xFC, 0xFC, /* b */
+            0x3C, 0xFF, 0xC3, 0xC0, 0xC0, 0xC0, 0xC0, 0xC3, 0xFF, 0x3C, /* c */
+            0xFC, 0xFE, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xC3, 0xFE, 0xFC, /* d */
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, /* e */
+            0xFF, 0xFF, 0xC0, 0xC0, 0xFF, 0xFF, 0xC0, 0xC0, 0xC0, 0xC0, /* f */
+        ];
+
+        for (i, x) in sprites.iter().enumerate() {
+            self.memory[FONT_ADDRESS + i] = *x;
+        }
 
-        for x in sprites.iter() {
-            self.memory[i] = x.clone();
-            i = i + 1;
+        for (i, x) in big_sprites.iter().enumerate() {
+            self.memory[BIG_FONT_ADDRESS + i] = *x;
         }
     }
 
-    pub fn load_program(&mut self) {
-        let mut file = File::open("roms/BC_test.ch8").unwrap();
-        let mut data: Vec<u8> = Vec::new();
-        file.read_to_end(&mut data).unwrap();
-
-        for (i, byte) in data.iter().enumerate() {
-            self.memory.insert(512 + i, byte.clone());
+    /// 64 KiB with the XO-CHIP extensions enabled, 4 KiB otherwise.
+    pub fn memory_size(&self) -> usize {
+        if self.quirks.xo_chip {
+            return XO_CHIP_MEMORY_SIZE;
         }
 
-        self.load_sprites();
+        return MEMORY_SIZE;
     }
 
-    pub fn update_timers(&mut self) {
-        if self.delay_timer > 0 {
-            self.delay_timer -= 1;
-        }
+    /// Loads a ROM file, see `rom::read` for the supported formats.
+    pub fn load_program(&mut self, path: &Path) -> Result<(), Chip8Error> {
+        let data = rom::read(path)?;
+
+        return self.load_rom(&data);
     }
 
-    pub fn cycle(&mut self) -> u8 {
-        for _i in 0..self.speed {
-            if !self.paused {
-                let higher = (self.memory[self.pc as usize] as u16) << 8;
-                let lower = self.memory[self.pc as usize + 1] as u16;
+    /// Copies a ROM to `load_address` and points `pc` at it. A ROM loaded
+    /// over the fonts replaces them.
+    pub fn load_rom(&mut self, data: &[u8]) -> Result<(), Chip8Error> {
+        self.memory.resize(self.memory_size(), 0);
+        self.load_sprites();
 
-                self.execute_instruction(higher + lower);
-            }
+        let start = self.load_address as usize;
+        if start > self.memory_size() {
+            return Err(Chip8Error::MemoryOutOfBounds {
+                address: self.load_address,
+                target: start,
+            });
         }
-        let event_pump = self.renderer.event_pump.borrow_mut();
-
-        for event in event_pump.poll_iter() {
-                match event {
-                    Event::Quit {..} |
-                    Event::KeyDown { keycode: Some(Keycode::Escape), .. } => {
-                       return 1;
-                    },
-                    ev => {
-                        match ev {
-                            Event::KeyDown { keycode, .. } => {
-                                self.renderer.keyboard.on_key_down(keycode.unwrap());
-                            },
-                            Event::KeyUp { keycode, .. } => {
-                                self.renderer.keyboard.on_key_up(keycode.unwrap());
-                            }
-                            _ => {
-                                return 0;
-                            }
-                        }
-                    }
-                }
+        let available = self.memory_size().saturating_sub(start);
+        if data.len() > available {
+            return Err(Chip8Error::RomTooLarge {
+                size: data.len(),
+                available,
+            });
         }
 
-        if !self.paused {
-            self.update_timers();
-        }
+        self.memory[start..start + data.len()].copy_from_slice(data);
+        self.pc = self.load_address;
+        self.rom_hash = savestate::rom_hash(data);
 
-        return 0;
+        return Ok(());
     }
 
-    fn get_type(&self, opcode: u16) -> &str {
-        match opcode & 0xF000 {
-            0x0000 => match opcode & 0xFF {
-                0xE0 => "Clear",
-
-                0xEE => "Return",
-
-                _ => panic!("Instruction not know: {}", opcode),
-            },
-            0x1000 => "Jump",
-
-            0x2000 => "Call",
-
-            0x3000 => "Skip if Equals Byte",
-
-            0x4000 => "Skip if not equals",
-
-            0x5000 => "Skip if Equals",
-
-            0x6000 => "Load byte",
+    /// Ends a 60 Hz frame: counts the timers down and clears the keypad edges.
+    pub fn update_timers(&mut self) {
+        self.vblank = true;
+        self.keyboard.clear_edges();
 
-            0x7000 => "Add byte",
+        if self.delay_timer > 0 {
+            self.delay_timer -= 1;
+        }
 
-            0x8000 => match opcode & 0xF {
-                0x0 => "Move",
+        if self.sound_timer > 0 {
+            self.sound_timer -= 1;
+        }
+    }
 
-                0x1 => "Or",
+    pub fn cycle(&mut self) -> Result<(), Chip8Error> {
+        if self.paused {
+            return Ok(());
+        }
 
-                0x2 => "And",
+        for _i in 0..self.speed {
+            self.step()?;
+        }
 
-                0x3 => "Xor",
+        self.update_timers();
 
-                0x4 => "Add",
+        return Ok(());
+    }
 
-                0x5 => "Sub",
+    /// Executes the instruction at `pc`. On error `pc` still points at the
+    /// failing instruction.
+    pub fn step(&mut self) -> Result<(), Chip8Error> {
+        self.last_write = None;
+        let opcode = self.read_word(self.pc)?;
 
-                0x6 => "Shift Right",
+        return self.execute_instruction(opcode);
+    }
 
-                0x7 => "Reverse sub",
+    pub fn read_word(&self, address: u16) -> Result<u16, Chip8Error> {
+        self.check_memory(address as usize, 2)?;
 
-                0xE => "Shift left",
-                _ => panic!("Instruction not know: {}", opcode),
-            },
+        let higher = (self.memory[address as usize] as u16) << 8;
+        let lower = self.memory[address as usize + 1] as u16;
 
-            0x9000 => "Skip if not equal",
+        return Ok(higher + lower);
+    }
 
-            0xA000 => "Load i",
+    /// Fails unless `length` bytes starting at `start` are inside memory.
+    fn check_memory(&self, start: usize, length: usize) -> Result<(), Chip8Error> {
+        if start + length > self.memory.len() {
+            return Err(Chip8Error::MemoryOutOfBounds {
+                address: self.pc,
+                target: start + length - 1,
+            });
+        }
 
-            0xB000 => "Jump Plus Zero",
+        return Ok(());
+    }
 
-            0xC000 => "Random",
+    fn unknown_opcode(&self, opcode: u16) -> Chip8Error {
+        return Chip8Error::UnknownOpcode {
+            opcode,
+            address: self.pc,
+        };
+    }
 
-            0xD000 => "Draw",
+    /// The address `length` bytes past `pc`, failing past the end of the
+    /// 16-bit address space.
+    fn address_after(&self, length: u16) -> Result<u16, Chip8Error> {
+        return self.pc.checked_add(length).ok_or(Chip8Error::MemoryOutOfBounds {
+            address: self.pc,
+            target: self.pc as usize + length as usize,
+        });
+    }
 
-            0xE000 => match opcode & 0xFF {
-                0x9E => "Skip if pressed",
+    /// Moves past the current instruction and, when `skip` is set, the next
+    /// one, which is four bytes long for the XO-CHIP `F000 NNNN`.
+    fn skip_next_instruction_if(&mut self, skip: bool) -> Result<(), Chip8Error> {
+        let next = self.address_after(2)?;
+        if !skip {
+            self.pc = next;
+        } else if self.read_word(next) == Ok(0xF000) {
+            self.pc = self.address_after(6)?;
+        } else {
+            self.pc = self.address_after(4)?;
+        }
 
-                0xA1 => "Skip if not pressed",
-                _ => panic!("Instruction not know: {}", opcode),
-            },
+        return Ok(());
+    }
 
-            0xF000 => match opcode & 0xFF {
-                0x07 => "Load Delay Timer",
-                0x0A => "Wait for key press",
-                0x15 => "Set delay timer",
-                0x18 => "Set sound timer",
-                0x1E => "Add to i",
-                0x29 => "Load Sprite",
-                0x33 => "BCD Representation",
-                0x55 => "Store register",
-                0x65 => "Load Register",
-                _ => panic!("Instruction not know: {}", opcode),
-            },
-            _ => panic!("Instruction not know: {}", opcode),
+    /// Registers `Vx` to `Vy` inclusive, counting down when `x > y`.
+    fn register_range(x: u16, y: u16) -> Box<dyn Iterator<Item = usize>> {
+        let (x, y) = (x as usize, y as usize);
+        if x <= y {
+            return Box::new(x..=y);
         }
+
+        return Box::new((y..=x).rev());
     }
 
-    fn execute_instruction(&mut self, opcode: u16) {
-        println!("Type: {:X?} - {:X?}", self.get_type(opcode), opcode);
+    /// Human readable name of an instruction, for tracing.
+    pub fn get_type(&self, opcode: u16) -> Result<&'static str, Chip8Error> {
+        return match opcodes::decode(opcode) {
+            Some(entry) => Ok(entry.name),
+            None => Err(self.unknown_opcode(opcode)),
+        };
+    }
 
+    fn execute_instruction(&mut self, opcode: u16) -> Result<(), Chip8Error> {
         let x = opcode >> 8 & 0xF;
         let y = opcode >> 4 & 0xF;
 
-        match opcode & 0xF000 {
-            0x0000 => match opcode & 0xFF {
-                0xE0 => {
-                    self.renderer.clear();
-                    self.pc = self.pc + 2;
-                }
-                0xEE => {
-                    self.pc = self.stack.pop().unwrap();
+        let entry = opcodes::decode(opcode).ok_or_else(|| self.unknown_opcode(opcode))?;
+
+        match entry.op {
+            Op::Clear => {
+                self.framebuffer.clear();
+                self.pc = self.address_after(2)?;
+            }
+            Op::Return => {
+                if self.sp == 0 {
+                    return Err(Chip8Error::StackUnderflow { address: self.pc });
                 }
-                _ => panic!("Instruction not know: {}", opcode),
-            },
-            0x1000 => {
+                self.sp -= 1;
+                self.pc = self.stack[self.sp];
+            }
+            Op::ScrollDown => {
+                self.framebuffer.scroll_down((opcode & 0xF) as isize);
+                self.pc = self.address_after(2)?;
+            }
+            Op::ScrollUp => {
+                self.framebuffer.scroll_up((opcode & 0xF) as isize);
+                self.pc = self.address_after(2)?;
+            }
+            Op::ScrollRight => {
+                self.framebuffer.scroll_right(4);
+                self.pc = self.address_after(2)?;
+            }
+            Op::ScrollLeft => {
+                self.framebuffer.scroll_left(4);
+                self.pc = self.address_after(2)?;
+            }
+            Op::Exit => {
+                self.exited = true;
+                self.pc = self.address_after(2)?;
+            }
+            Op::LowResolution => {
+                self.framebuffer.set_hires(false);
+                self.pc = self.address_after(2)?;
+            }
+            Op::HighResolution => {
+                self.framebuffer.set_hires(true);
+                self.pc = self.address_after(2)?;
+            }
+            Op::Jump => {
                 self.pc = opcode & 0xFFF;
             }
 
-            0x2000 => {
-                self.stack.push(self.pc);
+            Op::Call => {
+                if self.sp >= self.stack.len() {
+                    return Err(Chip8Error::StackOverflow { address: self.pc });
+                }
+                // Return to the instruction after the call.
+                self.stack[self.sp] = self.address_after(2)?;
+                self.sp += 1;
                 self.pc = opcode & 0xFFF;
             }
 
-            0x3000 => {
-                if self.v[x as usize] == (opcode as u8 & 0xFF) {
-                    self.pc += 4;
-                } else {
-                    self.pc += 2;
-                }
+            Op::SkipEqualByte => {
+                self.skip_next_instruction_if(self.v[x as usize] == opcode as u8)?;
+            }
+
+            Op::SkipNotEqualByte => {
+                self.skip_next_instruction_if(self.v[x as usize] != opcode as u8)?;
+            }
+
+            Op::SkipEqual => {
+                self.skip_next_instruction_if(self.v[x as usize] == self.v[y as usize])?;
             }
 
-            0x4000 => {
-                if self.v[x as usize] != (opcode as u8 & 0xFF) {
-                    self.pc += 4;
-                } else {
-                    self.pc += 2;
+            Op::SaveRange => {
+                let count = Cpu::register_range(x, y).count();
+                self.check_memory(self.i as usize, count)?;
+                self.last_write = Some(self.i as usize..self.i as usize + count);
+                for (offset, register) in Cpu::register_range(x, y).enumerate() {
+                    let address = self.i as usize + offset;
+                    self.memory[address] = self.v[register];
                 }
+                self.pc = self.address_after(2)?;
             }
 
-            0x5000 => {
-                if self.v[x as usize] == self.v[y as usize] {
-                    self.pc += 4;
-                } else {
-                    self.pc += 2;
+            Op::LoadRange => {
+                self.check_memory(self.i as usize, Cpu::register_range(x, y).count())?;
+                for (offset, register) in Cpu::register_range(x, y).enumerate() {
+                    let address = self.i as usize + offset;
+                    self.v[register] = self.memory[address];
                 }
+                self.pc = self.address_after(2)?;
             }
 
-            0x6000 => {
-                self.v[x as usize] = opcode as u8 & 0xFF;
-                self.pc += 2;
+            Op::LoadByte => {
+                self.v[x as usize] = opcode as u8;
+                self.pc = self.address_after(2)?;
             }
 
-            0x7000 => {
-                self.v[x as usize] = self.v[x as usize].wrapping_add(opcode as u8 & 0xFF);
-                self.pc += 2;
+            Op::AddByte => {
+                self.v[x as usize] = self.v[x as usize].wrapping_add(opcode as u8);
+                self.pc = self.address_after(2)?;
             }
 
-            0x8000 => match opcode & 0xF {
-                0x0 => {
-                    self.v[x as usize] = self.v[y as usize];
-                    self.pc += 2;
-                }
+            Op::Move => {
+                self.v[x as usize] = self.v[y as usize];
+                self.pc = self.address_after(2)?;
+            }
 
-                0x1 => {
-                    self.v[x as usize] = self.v[x as usize] | self.v[y as usize];
-                    self.pc += 2;
+            Op::Or => {
+                self.v[x as usize] |= self.v[y as usize];
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
                 }
+                self.pc = self.address_after(2)?;
+            }
 
-                0x2 => {
-                    self.v[x as usize] &= self.v[y as usize];
-                    self.pc += 2;
+            Op::And => {
+                self.v[x as usize] &= self.v[y as usize];
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
                 }
+                self.pc = self.address_after(2)?;
+            }
 
-                0x3 => {
-                    self.v[x as usize] ^= self.v[y as usize];
-                    self.pc += 2;
+            Op::Xor => {
+                self.v[x as usize] ^= self.v[y as usize];
+                if self.quirks.vf_reset {
+                    self.v[0xF] = 0;
                 }
+                self.pc = self.address_after(2)?;
+            }
 
-                0x4 => {
-                    let sum = self.v[x as usize] as u16 + self.v[y as usize] as u16;
+            // The flag is written after the result, so it wins when X is F.
+            Op::Add => {
+                let (sum, carry) = self.v[x as usize].overflowing_add(self.v[y as usize]);
 
-                    if sum > 0xff {
-                        self.v[0xf] = 1;
-                    } else {
-                        self.v[0xf] = 0;
-                    }
+                self.v[x as usize] = sum;
+                self.v[0xF] = carry as u8;
+                self.pc = self.address_after(2)?;
+            }
 
-                    self.v[x as usize] = sum as u8;
-                    self.pc += 2;
-                }
+            Op::Sub => {
+                let value1 = self.v[x as usize];
+                let value2 = self.v[y as usize];
 
-                0x5 => {
-                    self.v[0xF] = 0;
+                self.v[x as usize] = value1.wrapping_sub(value2);
+                self.v[0xF] = (value1 >= value2) as u8;
+                self.pc = self.address_after(2)?;
+            }
 
-                    if self.v[x as usize] > self.v[y as usize] {
-                        self.v[0xF] = 1;
-                    }
+            Op::ShiftRight => {
+                let value = self.shift_source(x, y);
 
-                    let value1 = self.v[x as usize];
-                    let value2 = self.v[y as usize];
+                self.v[x as usize] = value >> 1;
+                self.v[0xF] = value & 0x1;
+                self.pc = self.address_after(2)?;
+            }
 
-                    self.v[x as usize] = value1.wrapping_sub(value2);
-                    self.pc += 2;
-                }
+            Op::ReverseSub => {
+                let value1 = self.v[x as usize];
+                let value2 = self.v[y as usize];
 
-                0x6 => {
-                    self.v[0xF] = self.v[x as usize] & 0x1;
+                self.v[x as usize] = value2.wrapping_sub(value1);
+                self.v[0xF] = (value2 >= value1) as u8;
+                self.pc = self.address_after(2)?;
+            }
 
-                    self.v[x as usize] >>= 1;
-                    self.pc += 2;
-                }
+            Op::ShiftLeft => {
+                let value = self.shift_source(x, y);
 
-                0x7 => {
-                    self.v[0xF] = 0;
+                self.v[x as usize] = value << 1;
+                self.v[0xF] = value >> 7;
+                self.pc = self.address_after(2)?;
+            }
 
-                    if self.v[y as usize] > self.v[x as usize] {
-                        self.v[0xF] = 1;
-                    }
+            Op::SkipNotEqual => {
+                self.skip_next_instruction_if(self.v[x as usize] != self.v[y as usize])?;
+            }
 
-                    self.v[y as usize] = self.v[y as usize].wrapping_sub(self.v[x as usize]);
-                    self.pc += 2;
-                }
+            Op::LoadI => {
+                self.i = opcode & 0xFFF;
+                self.pc = self.address_after(2)?;
+            }
 
-                0xE => {
-                    self.v[0xF] = self.v[x as usize] >> 7;
-                    self.v[x as usize] <<= 1;
-                    self.pc += 2;
-                }
-                _ => {
-                    panic!("Instruction not know: {}", opcode);
-                }
-            },
+            Op::JumpOffset => {
+                let offset = if self.quirks.jump_uses_vx { self.v[x as usize] } else { self.v[0] };
 
-            0x9000 => {
-                if self.v[x as usize] != self.v[y as usize] {
-                    self.pc += 4;
-                } else {
-                    self.pc += 2;
+                self.pc = (opcode & 0xFFF) + offset as u16;
+            }
+
+            Op::Random => {
+                let ran = self.rng.next_u8();
+
+                self.v[x as usize] = ran & opcode as u8;
+                self.pc = self.address_after(2)?;
+            }
+
+            Op::Draw => {
+                if self.quirks.display_wait && !self.vblank {
+                    // Retry the same instruction once the next frame starts.
+                    return Ok(());
                 }
+                self.vblank = false;
+
+                // DXY0 draws a 16x16 SUPER-CHIP sprite stored as two bytes per row.
+                let (width, height) = match opcode & 0xF {
+                    0 => (16, 16),
+                    rows => (8, rows),
+                };
+                self.draw_sprite(self.v[x as usize], self.v[y as usize], width, height)?;
+
+                self.pc = self.address_after(2)?;
             }
 
-            0xA000 => {
-                self.i = opcode & 0xFFF;
-                self.pc += 2;
+            Op::SkipPressed => {
+                self.skip_next_instruction_if(self.keyboard.is_key_pressed(self.v[x as usize]))?;
             }
 
-            0xB000 => {
-                self.pc = (opcode & 0xFF) + self.v[0] as u16;
-                self.pc += 2;
+            Op::SkipNotPressed => {
+                self.skip_next_instruction_if(!self.keyboard.is_key_pressed(self.v[x as usize]))?;
             }
 
-            0xC000 => {
-                let ran: u8 = rand::random();
+            Op::LoadLongI => {
+                self.i = self.read_word(self.address_after(2)?)?;
+                self.pc = self.address_after(4)?;
+            }
 
-                self.v[x as usize] = ran & (opcode as u8 & 0xff);
-                self.pc += 2;
+            Op::SelectPlanes => {
+                self.framebuffer.planes = x as u32;
+                self.pc = self.address_after(2)?;
             }
 
-            0xD000 => {
-                let width: u8 = 8;
-                let height: u8 = opcode as u8 & 0xf;
+            Op::LoadAudio => {
+                let start = self.i as usize;
+                self.check_memory(start, 16)?;
+                self.audio_pattern.copy_from_slice(&self.memory[start..start + 16]);
+                self.pc = self.address_after(2)?;
+            }
 
-                self.v[0xF] = 0;
+            Op::LoadDelay => {
+                self.v[x as usize] = self.delay_timer;
 
-                for row in 0..height {
-                    let index = self.i + row as u16;
-                    let mut sprite = self.memory[index as usize];
+                self.pc = self.address_after(2)?;
+            }
 
-                    for column in 0..width {
-                        let msb = 1 << (8 - 1);
-                        if (sprite & msb) > 0 {
-                            let x_pos = self.v[x as usize] + column;
-                            let y_pos = self.v[y as usize] + row;
-                            if self.renderer.set_pixel(x_pos as isize, y_pos as isize) {
-                                self.v[0xF] = 1;
-                            }
-                        }
+            Op::WaitKey => {
+                let keys = self.keyboard.mask();
 
-                        sprite <<= 1;
+                // Driven by the edges, so a key tapped between two polls is
+                // not missed, and cleared on entry so one tap is not read by
+                // two waits.
+                self.key_wait = match self.key_wait {
+                    KeyWait::Idle => {
+                        self.keyboard.clear_edges();
+                        KeyWait::Waiting { held: keys }
+                    }
+                    KeyWait::Waiting { held } => {
+                        let held = held & keys & !self.keyboard.released;
+                        match self.keyboard.pressed & !held {
+                            0 => KeyWait::Waiting { held },
+                            pressed => KeyWait::Pressed(pressed.trailing_zeros() as u8),
+                        }
+                    }
+                    pressed => pressed,
+                };
+
+                // Timers keep running while the instruction repeats.
+                if let KeyWait::Pressed(key) = self.key_wait {
+                    if !self.quirks.key_wait_release || keys & (1 << key) == 0 {
+                        self.v[x as usize] = key;
+                        self.key_wait = KeyWait::Idle;
+                        self.pc = self.address_after(2)?;
                     }
                 }
-                self.renderer.render();
+            }
 
-                self.pc += 2;
+            Op::SetDelay => {
+                self.delay_timer = self.v[x as usize];
+                self.pc = self.address_after(2)?;
             }
 
-            0xE000 => match opcode & 0xFF {
-                0x9E => {
-                    if self.renderer.keyboard.is_key_pressed(self.v[x as usize]) > 0 {
-                        self.pc += 4;
-                    } else {
-                        self.pc += 2;
-                    }
-                }
+            Op::SetSound => {
+                self.sound_timer = self.v[x as usize];
+                self.pc = self.address_after(2)?;
+            }
 
-                0xA1 => {
-                    if !self.renderer.keyboard.is_key_pressed(self.v[x as usize]) == 0 {
-                        self.pc += 4;
-                    } else {
-                        self.pc += 2;
-                    }
-                }
-                _ => {
-                    panic!("Instruction not know: {}", opcode);
-                }
-            },
+            Op::AddI => {
+                self.i = self.i.wrapping_add(self.v[x as usize] as u16);
+                self.pc = self.address_after(2)?;
+            }
 
-            0xF000 => match opcode & 0xFF {
-                0x07 => {
-                    self.v[x as usize] = self.delay_timer;
+            Op::LoadFont => {
+                let value: u16 = self.v[x as usize].into();
+                self.i = value * 5;
+                self.pc = self.address_after(2)?;
+            }
 
-                    self.pc += 2;
-                }
+            Op::LoadBigFont => {
+                let digit = (self.v[x as usize] & 0xF) as u16;
+                self.i = BIG_FONT_ADDRESS as u16 + digit * 10;
+                self.pc = self.address_after(2)?;
+            }
 
-                0x0A => {
-                    if !self.renderer.keyboard.keys_pressed.is_empty() {
-                        self.v[x as usize] = self.renderer.keyboard.last_key_pressed;
+            Op::SetPitch => {
+                self.pitch = self.v[x as usize];
+                self.pc = self.address_after(2)?;
+            }
 
-                        self.pc += 2;
-                    }
-                }
+            Op::Bcd => {
+                self.check_memory(self.i as usize, 3)?;
+                self.last_write = Some(self.i as usize..self.i as usize + 3);
 
-                0x15 => {
-                    self.delay_timer = self.v[x as usize];
-                    self.pc += 2;
-                }
+                // Get the hundreds digit and place it in I.
+                let hundred = self.v[x as usize] / 100;
 
-                0x18 => {
-                    //ignored
-                    //self.soundTimer = self.v[x];
-                    self.pc += 2;
-                }
+                self.memory[self.i as usize] = hundred;
+
+                // Get tens digit and place it in I+1. Gets a value between 0 and 99,
+                // then divides by 10 to give us a value between 0 and 9.
+                let memory_1 = self.i + 1;
+
+                let dezen = (self.v[x as usize] % 100) / 10;
+                self.memory[memory_1 as usize] = dezen;
+
+                // Get the value of the ones (last) digit and place it in I+2.
+                let memory_2 = self.i + 2;
+                let unit = self.v[x as usize] % 10;
+                self.memory[memory_2 as usize] = unit;
+                self.pc = self.address_after(2)?;
+            }
+
+            Op::Store => {
+                self.check_memory(self.i as usize, x as usize + 1)?;
+                self.last_write = Some(self.i as usize..self.i as usize + x as usize + 1);
+                for register_index in 0..(x + 1) {
+                    let memory_index = self.i + register_index;
 
-                0x1E => {
-                    self.i += self.v[x as usize] as u16;
-                    self.pc += 2;
+                    self.memory[memory_index as usize] = self.v[register_index as usize];
+                }
+                if self.quirks.load_store_increments_i {
+                    self.i = self.i.wrapping_add(x + 1);
                 }
+                self.pc = self.address_after(2)?;
+            }
+
+            Op::Load => {
+                self.check_memory(self.i as usize, x as usize + 1)?;
+                for register_index in 0..(x + 1) {
+                    let v_index = self.i + register_index;
 
-                0x29 => {
-                    let value: u16 = self.v[x as usize].into();
-                    self.i = value * 5;
-                    self.pc += 2;
+                    self.v[register_index as usize] = self.memory[v_index as usize];
                 }
+                if self.quirks.load_store_increments_i {
+                    self.i = self.i.wrapping_add(x + 1);
+                }
+                self.pc = self.address_after(2)?;
+            }
 
-                0x33 => {
-                    // Get the hundreds digit and place it in I.
-                    let hundred = self.v[x as usize] / 100;
+            Op::StoreFlags => {
+                let count = x as usize + 1;
+                self.rpl[..count].copy_from_slice(&self.v[..count]);
+                self.pc = self.address_after(2)?;
+            }
 
-                    self.memory[self.i as usize] = hundred;
+            Op::LoadFlags => {
+                let count = x as usize + 1;
+                self.v[..count].copy_from_slice(&self.rpl[..count]);
+                self.pc = self.address_after(2)?;
+            }
+        }
 
-                    // Get tens digit and place it in I+1. Gets a value between 0 and 99,
-                    // then divides by 10 to give us a value between 0 and 9.
-                    let memory_1 = self.i + 1;
+        return Ok(());
+    }
 
-                    let dezen = (self.v[x as usize] % 100) / 10;
-                    self.memory[memory_1 as usize] = dezen;
+    fn shift_source(&self, x: u16, y: u16) -> u8 {
+        if self.quirks.shift_uses_vy {
+            return self.v[y as usize];
+        }
 
-                    // Get the value of the ones (last) digit and place it in I+2.
-                    let memory_2 = self.i + 2;
-                    let unit = self.v[x as usize] % 10;
-                    self.memory[memory_2 as usize] = unit;
-                    self.pc += 2;
-                }
+        return self.v[x as usize];
+    }
 
-                0x55 => {
-                    for register_index in 0..(x + 1) {
-                        let memory_index = self.i + register_index;
+    /// XORs a sprite from `I` onto every selected bitplane, setting `VF`
+    /// when any lit pixel is erased. With several planes selected the sprite
+    /// data for each plane follows the previous one in memory. The start
+    /// position always wraps; the part of the sprite crossing the edge is
+    /// clipped or wrapped per quirk.
+    fn draw_sprite(&mut self, x: u8, y: u8, width: u16, height: u16) -> Result<(), Chip8Error> {
+        let screen_width = self.framebuffer.width;
+        let screen_height = self.framebuffer.height;
+        let x_start = x as isize % screen_width;
+        let y_start = y as isize % screen_height;
+        let bytes_per_row = width as usize / 8;
+        let sprite_size = height as usize * bytes_per_row;
+        let mut address = self.i as usize;
+
+        self.check_memory(address, sprite_size * self.framebuffer.planes.count_ones() as usize)?;
+        self.v[0xF] = 0;
+
+        for plane in (0..PLANES).map(|bit| 1 << bit) {
+            if self.framebuffer.planes & plane == 0 {
+                continue;
+            }
 
-                        self.memory[memory_index as usize] = self.v[register_index as usize];
-                    }
-                    self.pc += 2;
+            for row in 0..height {
+                let y_pos = y_start + row as isize;
+
+                if self.quirks.clip_sprites && y_pos >= screen_height {
+                    continue;
                 }
 
-                0x65 => {
-                    for register_index in 0..(x + 1) {
-                        let v_index = self.i + register_index;
+                for column in 0..width {
+                    let sprite = self.memory[address + row as usize * bytes_per_row + column as usize / 8];
 
-                        self.v[register_index as usize] = self.memory[v_index as usize];
+                    if sprite & (0x80 >> (column % 8)) == 0 {
+                        continue;
+                    }
+
+                    let x_pos = x_start + column as isize;
+                    if self.quirks.clip_sprites && x_pos >= screen_width {
+                        continue;
                     }
-                    self.pc += 2;
-                }
 
-                _ => {
-                    panic!("Instruction not know: {}", opcode);
+                    if self.framebuffer.set_pixel(x_pos, y_pos, plane) {
+                        self.v[0xF] = 1;
+                    }
                 }
-            },
-            _ => {
-                panic!("Instruction not know: {}", opcode);
             }
+
+            address += sprite_size;
         }
+
+        return Ok(());
     }
 }
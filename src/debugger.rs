@@ -0,0 +1,359 @@
+use crate::cpu::Cpu;
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+pub const HELP: &str = "Debugger commands:
+    c, continue          Resume execution
+    p, pause             Pause execution
+    s, step [N]          Execute N instructions (default: 1)
+    f, frame             Execute one 60 Hz frame
+    bs, back [N]         Step N instructions backward (default: 1)
+    bf, backframe        Go back to the previous frame or debugger step
+    b, break ADDR        Break when PC reaches ADDR
+    w, watch ADDR        Break after an instruction writes to ADDR
+    cond REG OP VALUE    Break when a register condition becomes true,
+                         e.g. `cond V3 == 0x10` (REG: V0-VF, I, DT, ST)
+    l, list              List breakpoints
+    d, delete N          Delete breakpoint N from the list
+    r, regs              Show registers, stack and timers
+    m, mem ADDR [LEN]    Dump LEN bytes of memory (default: 16)
+    t, trace             Toggle printing every executed instruction
+    h, help              Show this message";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Register {
+    V(usize),
+    I,
+    DelayTimer,
+    SoundTimer,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Condition {
+    pub register: Register,
+    pub comparison: Comparison,
+    pub value: u16,
+    text: String,
+}
+
+impl Condition {
+    /// Parses conditions such as `V3 == 0x10` or `I >= 0x300`.
+    pub fn parse(text: &str) -> Result<Condition, String> {
+        let parts: Vec<&str> = text.split_whitespace().collect();
+        if parts.len() != 3 {
+            return Err(format!("Invalid condition '{}', expected e.g. 'V3 == 0x10'", text));
+        }
+
+        let register = match parts[0].to_uppercase().as_str() {
+            "I" => Register::I,
+            "DT" => Register::DelayTimer,
+            "ST" => Register::SoundTimer,
+            name if name.len() == 2 && name.starts_with('V') => {
+                let index = usize::from_str_radix(&name[1..], 16)
+                    .map_err(|_| format!("Unknown register '{}'", parts[0]))?;
+                Register::V(index)
+            }
+            _ => return Err(format!("Unknown register '{}'", parts[0])),
+        };
+
+        let comparison = match parts[1] {
+            "==" => Comparison::Equal,
+            "!=" => Comparison::NotEqual,
+            "<" => Comparison::Less,
+            "<=" => Comparison::LessOrEqual,
+            ">" => Comparison::Greater,
+            ">=" => Comparison::GreaterOrEqual,
+            _ => return Err(format!("Unknown comparison '{}'", parts[1])),
+        };
+
+        return Ok(Condition {
+            register,
+            comparison,
+            value: parse_number(parts[2])?,
+            text: parts.join(" "),
+        });
+    }
+
+    pub fn holds(&self, cpu: &Cpu) -> bool {
+        let current = match self.register {
+            Register::V(index) => cpu.v[index] as u16,
+            Register::I => cpu.i,
+            Register::DelayTimer => cpu.delay_timer as u16,
+            Register::SoundTimer => cpu.sound_timer as u16,
+        };
+
+        return match self.comparison {
+            Comparison::Equal => current == self.value,
+            Comparison::NotEqual => current != self.value,
+            Comparison::Less => current < self.value,
+            Comparison::LessOrEqual => current <= self.value,
+            Comparison::Greater => current > self.value,
+            Comparison::GreaterOrEqual => current >= self.value,
+        };
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Breakpoint {
+    /// Stops before the instruction at this address runs.
+    Address(u16),
+    /// Stops after an instruction writes to this memory address.
+    Watch(u16),
+    /// Stops before the next instruction once the condition turns true.
+    Condition(Condition),
+}
+
+/// What a REPL line asks the emulator to do.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DebugCommand {
+    Continue,
+    Pause,
+    Step(u32),
+    Frame,
+    StepBack(u32),
+    FrameBack,
+    Add(Breakpoint),
+    List,
+    Delete(usize),
+    Registers,
+    Memory(u16, u16),
+    Trace,
+    Help,
+}
+
+pub struct Debugger {
+    pub breakpoints: Vec<Breakpoint>,
+    pub trace: bool,
+    /// Whether each condition held before the previous instruction, so a
+    /// condition only breaks when it becomes true and not on every step.
+    condition_state: Vec<bool>,
+    /// The address execution resumed at, whose breakpoint must not fire again.
+    resumed_at: Option<u16>,
+}
+
+impl Debugger {
+    pub fn new() -> Debugger {
+        return Debugger {
+            breakpoints: Vec::new(),
+            trace: false,
+            condition_state: Vec::new(),
+            resumed_at: None,
+        };
+    }
+
+    pub fn parse(line: &str) -> Result<DebugCommand, String> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or("");
+        let rest: Vec<&str> = words.collect();
+        let argument = |index: usize| -> Result<u16, String> {
+            let text = rest
+                .get(index)
+                .ok_or(format!("'{}' needs an argument, see 'help'", command))?;
+            return parse_number(text);
+        };
+
+        return match command {
+            "c" | "continue" => Ok(DebugCommand::Continue),
+            "p" | "pause" => Ok(DebugCommand::Pause),
+            "s" | "step" => match rest.first() {
+                Some(_) => Ok(DebugCommand::Step(argument(0)? as u32)),
+                None => Ok(DebugCommand::Step(1)),
+            },
+            "f" | "frame" => Ok(DebugCommand::Frame),
+            "bs" | "back" => match rest.first() {
+                Some(_) => Ok(DebugCommand::StepBack(argument(0)? as u32)),
+                None => Ok(DebugCommand::StepBack(1)),
+            },
+            "bf" | "backframe" => Ok(DebugCommand::FrameBack),
+            "b" | "break" => Ok(DebugCommand::Add(Breakpoint::Address(argument(0)?))),
+            "w" | "watch" => Ok(DebugCommand::Add(Breakpoint::Watch(argument(0)?))),
+            "cond" => Ok(DebugCommand::Add(Breakpoint::Condition(Condition::parse(&rest.join(" "))?))),
+            "l" | "list" => Ok(DebugCommand::List),
+            "d" | "delete" => Ok(DebugCommand::Delete(argument(0)? as usize)),
+            "r" | "regs" => Ok(DebugCommand::Registers),
+            "m" | "mem" => match rest.get(1) {
+                Some(_) => Ok(DebugCommand::Memory(argument(0)?, argument(1)?)),
+                None => Ok(DebugCommand::Memory(argument(0)?, 16)),
+            },
+            "t" | "trace" => Ok(DebugCommand::Trace),
+            "h" | "help" | "?" => Ok(DebugCommand::Help),
+            _ => Err(format!("Unknown command '{}', see 'help'", command)),
+        };
+    }
+
+    pub fn add(&mut self, breakpoint: Breakpoint) {
+        self.breakpoints.push(breakpoint);
+        self.condition_state.push(false);
+    }
+
+    pub fn delete(&mut self, index: usize) -> Result<(), String> {
+        if index >= self.breakpoints.len() {
+            return Err(format!("No breakpoint {}", index));
+        }
+
+        self.breakpoints.remove(index);
+        self.condition_state.remove(index);
+
+        return Ok(());
+    }
+
+    pub fn list(&self) -> String {
+        if self.breakpoints.is_empty() {
+            return String::from("No breakpoints");
+        }
+
+        let lines: Vec<String> = self
+            .breakpoints
+            .iter()
+            .enumerate()
+            .map(|(index, breakpoint)| match breakpoint {
+                Breakpoint::Address(address) => format!("{}: break at 0x{:03X}", index, address),
+                Breakpoint::Watch(address) => format!("{}: watch 0x{:03X}", index, address),
+                Breakpoint::Condition(condition) => format!("{}: when {}", index, condition.text),
+            })
+            .collect();
+
+        return lines.join("\n");
+    }
+
+    /// Called when execution resumes, so the breakpoint at `pc` lets it go.
+    pub fn resume(&mut self, cpu: &Cpu) {
+        self.resumed_at = Some(cpu.pc);
+    }
+
+    /// Checks the breakpoints that fire before the instruction at `pc`.
+    pub fn check_before(&mut self, cpu: &Cpu) -> Option<String> {
+        let resumed_at = self.resumed_at.take();
+        let mut hit = None;
+
+        for (index, breakpoint) in self.breakpoints.iter().enumerate() {
+            match breakpoint {
+                Breakpoint::Address(address) => {
+                    if *address == cpu.pc && resumed_at != Some(cpu.pc) && hit.is_none() {
+                        hit = Some(format!("Breakpoint at 0x{:03X}", address));
+                    }
+                }
+                Breakpoint::Condition(condition) => {
+                    let holds = condition.holds(cpu);
+                    if holds && !self.condition_state[index] && hit.is_none() {
+                        hit = Some(format!("Condition {} at 0x{:03X}", condition.text, cpu.pc));
+                    }
+                    self.condition_state[index] = holds;
+                }
+                Breakpoint::Watch(_) => {}
+            }
+        }
+
+        return hit;
+    }
+
+    /// Checks the watchpoints against the memory written by the instruction
+    /// that just ran at `address`.
+    pub fn check_after(&self, cpu: &Cpu, address: u16) -> Option<String> {
+        let written = cpu.last_write.clone()?;
+
+        for breakpoint in self.breakpoints.iter() {
+            if let Breakpoint::Watch(watched) = breakpoint {
+                if written.contains(&(*watched as usize)) {
+                    return Some(format!(
+                        "Watchpoint 0x{:03X} written by instruction at 0x{:03X}",
+                        watched, address
+                    ));
+                }
+            }
+        }
+
+        return None;
+    }
+
+    pub fn registers(cpu: &Cpu) -> String {
+        let registers: Vec<String> = cpu
+            .v
+            .iter()
+            .enumerate()
+            .map(|(index, value)| format!("V{:X} {:02X}", index, value))
+            .collect();
+        let stack: Vec<String> = cpu.call_stack().iter().map(|address| format!("0x{:03X}", address)).collect();
+
+        return format!(
+            "PC 0x{:03X}  I 0x{:03X}  DT {:02X}  ST {:02X}\n{}\n{}\nStack [{}]",
+            cpu.pc,
+            cpu.i,
+            cpu.delay_timer,
+            cpu.sound_timer,
+            registers[..8].join("  "),
+            registers[8..].join("  "),
+            stack.join(", ")
+        );
+    }
+
+    pub fn memory(cpu: &Cpu, start: u16, length: u16) -> String {
+        let start = start as usize;
+        let end = (start + length as usize).min(cpu.memory.len());
+        let mut lines = Vec::new();
+
+        for row in (start..end).step_by(16) {
+            let bytes: Vec<String> = cpu.memory[row..(row + 16).min(end)]
+                .iter()
+                .map(|byte| format!("{:02X}", byte))
+                .collect();
+            lines.push(format!("0x{:03X}: {}", row, bytes.join(" ")));
+        }
+
+        return lines.join("\n");
+    }
+}
+
+impl Default for Debugger {
+    fn default() -> Debugger {
+        return Debugger::new();
+    }
+}
+
+/// Reads REPL lines from stdin on a background thread, so the emulator loop
+/// keeps running (and the window keeps responding) while waiting for input.
+pub fn spawn_console() -> Receiver<String> {
+    let (sender, receiver) = mpsc::channel();
+
+    thread::spawn(move || {
+        for line in io::stdin().lock().lines() {
+            match line {
+                Ok(line) => {
+                    if sender.send(line).is_err() {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+    });
+
+    return receiver;
+}
+
+/// Accepts `0x1F`, `$1F`, `#1F` as hex and plain digits as decimal.
+pub fn parse_number(text: &str) -> Result<u16, String> {
+    let invalid = |_| format!("Invalid number '{}'", text);
+
+    if let Some(hex) = text
+        .strip_prefix("0x")
+        .or_else(|| text.strip_prefix("0X"))
+        .or_else(|| text.strip_prefix('$'))
+        .or_else(|| text.strip_prefix('#'))
+    {
+        return u16::from_str_radix(hex, 16).map_err(invalid);
+    }
+
+    return text.parse().map_err(invalid);
+}
@@ -0,0 +1,169 @@
+use crate::opcodes::{self, Op};
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// One instruction or data byte of a disassembled ROM.
+pub struct Line {
+    pub address: u16,
+    pub bytes: Vec<u8>,
+    /// The instruction, or a `db` directive for bytes that are never executed.
+    pub text: String,
+}
+
+pub struct Disassembly {
+    pub lines: Vec<Line>,
+    /// Names for the jump, call and `LD I` targets inside the ROM.
+    pub labels: BTreeMap<u16, String>,
+}
+
+/// Disassembles a ROM loaded at `start`, following the control flow from
+/// there so that sprites and other data are not decoded as instructions.
+/// Bytes past the end of the 16-bit address space are left out.
+pub fn disassemble(rom: &[u8], start: u16) -> Disassembly {
+    let rom = &rom[..rom.len().min(0x10000 - start as usize)];
+    let end = start as usize + rom.len();
+    let contains = |address: u16| -> bool { address >= start && (address as usize) < end };
+    let word_at = |address: u16| -> Option<u16> {
+        if !contains(address) || address as usize + 1 >= end {
+            return None;
+        }
+        let offset = (address - start) as usize;
+
+        return Some(u16::from_be_bytes([rom[offset], rom[offset + 1]]));
+    };
+
+    // The size of the instruction starting at each offset, zero for data.
+    let mut sizes = vec![0u16; rom.len()];
+    let mut labels = BTreeMap::new();
+    let mut pending = vec![start];
+
+    while let Some(address) = pending.pop() {
+        if !contains(address) || sizes[(address - start) as usize] > 0 {
+            continue;
+        }
+
+        let opcode = match word_at(address) {
+            Some(opcode) => opcode,
+            None => continue,
+        };
+        let entry = match opcodes::decode(opcode) {
+            Some(entry) => entry,
+            None => continue,
+        };
+        if entry.size() == 4 && word_at(address.wrapping_add(2)).is_none() {
+            continue;
+        }
+
+        sizes[(address - start) as usize] = entry.size();
+        let next = address.wrapping_add(entry.size());
+        let target = opcode & 0xFFF;
+
+        match entry.op {
+            Op::Jump => {
+                if contains(target) {
+                    labels.entry(target).or_insert(format!("L{:03X}", target));
+                }
+                pending.push(target);
+            }
+            Op::Call => {
+                if contains(target) {
+                    labels.entry(target).or_insert(format!("sub_{:03X}", target));
+                }
+                pending.push(target);
+                pending.push(next);
+            }
+            Op::Return | Op::Exit => {}
+            Op::JumpOffset => {
+                // The destination depends on a register, so only the base of
+                // the jump table can be named.
+                if contains(target) {
+                    labels.entry(target).or_insert(format!("table_{:03X}", target));
+                }
+            }
+            Op::SkipEqualByte
+            | Op::SkipNotEqualByte
+            | Op::SkipEqual
+            | Op::SkipNotEqual
+            | Op::SkipPressed
+            | Op::SkipNotPressed => {
+                let skipped = match word_at(next) {
+                    Some(0xF000) => 4,
+                    _ => 2,
+                };
+                pending.push(next);
+                pending.push(next.wrapping_add(skipped));
+            }
+            Op::LoadI | Op::LoadLongI => {
+                let target = if entry.op == Op::LoadLongI { word_at(address.wrapping_add(2)).unwrap_or(0) } else { target };
+                if contains(target) {
+                    labels.entry(target).or_insert(format!("data_{:03X}", target));
+                }
+                pending.push(next);
+            }
+            _ => pending.push(next),
+        }
+    }
+
+    let mut lines = Vec::new();
+    let mut offset = 0;
+
+    while offset < rom.len() {
+        let address = start + offset as u16;
+        let size = sizes[offset] as usize;
+
+        // An instruction that overlaps another one or a label is shown as
+        // data, so that every label still lands on a line of its own.
+        let fits = size > 0
+            && (1..size).all(|i| sizes[offset + i] == 0 && !labels.contains_key(&(address + i as u16)));
+
+        if fits {
+            let opcode = word_at(address).unwrap_or(0);
+            let long = if size == 4 { word_at(address.wrapping_add(2)).unwrap_or(0) } else { 0 };
+            let entry = opcodes::decode(opcode).expect("only decoded instructions have a size");
+            let target = if size == 4 { long } else { opcode & 0xFFF };
+            let label = if entry.syntax.contains("nnn") { labels.get(&target) } else { None };
+
+            lines.push(Line {
+                address,
+                bytes: rom[offset..offset + size].to_vec(),
+                text: opcodes::format(entry, opcode, long, label.map(|label| label.as_str())),
+            });
+            offset += size;
+        } else {
+            lines.push(Line {
+                address,
+                bytes: vec![rom[offset]],
+                text: format!("db 0x{:02X}", rom[offset]),
+            });
+            offset += 1;
+        }
+    }
+
+    return Disassembly { lines, labels };
+}
+
+/// Prints the listing as assembly source, with the address, the raw bytes
+/// and, for data, the bits as sprite pixels in a comment on every line.
+impl fmt::Display for Disassembly {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for line in self.lines.iter() {
+            if let Some(label) = self.labels.get(&line.address) {
+                writeln!(f, "{}:", label)?;
+            }
+
+            let bytes: Vec<String> = line.bytes.iter().map(|byte| format!("{:02X}", byte)).collect();
+            let mut text = format!("    {:<24}; 0x{:03X}  {:<11}", line.text, line.address, bytes.join(" "));
+
+            if line.text.starts_with("db ") {
+                let pixels: String = (0..8)
+                    .map(|bit| if line.bytes[0] & (0x80 >> bit) != 0 { '#' } else { '.' })
+                    .collect();
+                text.push_str(&pixels);
+            }
+
+            writeln!(f, "{}", text.trim_end())?;
+        }
+
+        return Ok(());
+    }
+}
@@ -0,0 +1,55 @@
+use std::error::Error;
+use std::fmt;
+use std::path::PathBuf;
+
+/// Everything that can stop the interpreter from running a ROM.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Chip8Error {
+    /// The opcode at `address` is not part of any supported instruction set.
+    UnknownOpcode { opcode: u16, address: u16 },
+    /// `2NNN` with every stack level in use.
+    StackOverflow { address: u16 },
+    /// `00EE` with an empty stack.
+    StackUnderflow { address: u16 },
+    /// An instruction at `address` tried to access memory past the end.
+    MemoryOutOfBounds { address: u16, target: usize },
+    /// The ROM does not fit between its load address and the end of memory.
+    RomTooLarge { size: usize, available: usize },
+    /// The ROM file could not be read.
+    RomUnreadable { path: PathBuf, reason: String },
+}
+
+impl fmt::Display for Chip8Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Chip8Error::UnknownOpcode { opcode, address } => {
+                write!(f, "Unknown opcode {:04X} at 0x{:03X}", opcode, address)
+            }
+            Chip8Error::StackOverflow { address } => write!(f, "Stack overflow at 0x{:03X}", address),
+            Chip8Error::StackUnderflow { address } => {
+                write!(f, "Return with an empty stack at 0x{:03X}", address)
+            }
+            Chip8Error::MemoryOutOfBounds { address, target } => write!(
+                f,
+                "Instruction at 0x{:03X} accessed memory out of bounds at 0x{:X}",
+                address, target
+            ),
+            Chip8Error::RomTooLarge { size, available } => write!(
+                f,
+                "ROM is {} bytes, but only {} bytes fit in memory",
+                size, available
+            ),
+            Chip8Error::RomUnreadable { path, reason } => {
+                write!(f, "Could not read ROM '{}': {}", path.display(), reason)
+            }
+        }
+    }
+}
+
+impl Error for Chip8Error {}
+
+impl From<Chip8Error> for String {
+    fn from(error: Chip8Error) -> String {
+        return error.to_string();
+    }
+}
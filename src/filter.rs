@@ -0,0 +1,162 @@
+use crate::framebuffer::Framebuffer;
+use crate::palette::Palette;
+use std::collections::VecDeque;
+
+/// Frames averaged by `blend` without a count.
+pub const BLEND_FRAMES: usize = 3;
+
+/// Share of its glow a pixel keeps every frame after going dark in `decay`.
+const PERSISTENCE: f32 = 0.6;
+
+/// Names accepted by `Mode::from_name`.
+pub const MODES: [&str; 4] = ["off", "or", "blend", "decay"];
+
+/// How frames are combined before being shown.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Mode {
+    /// Shows every frame as it is.
+    Off,
+    /// Lights a pixel lit in this or the previous frame, which hides sprites
+    /// being erased and drawn again one frame later.
+    Or,
+    /// Averages the colors of the last N frames.
+    Blend(usize),
+    /// Lets pixels fade out like the phosphor of a CRT.
+    Decay,
+}
+
+impl Mode {
+    /// A mode by name, with `blend:N` averaging N frames.
+    pub fn from_name(name: &str) -> Result<Mode, String> {
+        let name = name.to_lowercase();
+
+        if let Some(frames) = name.strip_prefix("blend:") {
+            return match frames.parse::<usize>() {
+                Ok(frames) if frames > 0 => Ok(Mode::Blend(frames)),
+                _ => Err(format!("Invalid frame count '{}' for the blend filter", frames)),
+            };
+        }
+
+        return match name.as_str() {
+            "off" => Ok(Mode::Off),
+            "or" => Ok(Mode::Or),
+            "blend" => Ok(Mode::Blend(BLEND_FRAMES)),
+            "decay" => Ok(Mode::Decay),
+            _ => Err(format!("Unknown filter '{}', expected one of: {}", name, MODES.join(", "))),
+        };
+    }
+
+    pub fn name(&self) -> String {
+        return match self {
+            Mode::Off => String::from("off"),
+            Mode::Or => String::from("or"),
+            Mode::Blend(frames) => format!("blend:{}", frames),
+            Mode::Decay => String::from("decay"),
+        };
+    }
+
+    /// The mode the hotkey switches to.
+    pub fn next(&self) -> Mode {
+        return match self {
+            Mode::Off => Mode::Or,
+            Mode::Or => Mode::Blend(BLEND_FRAMES),
+            Mode::Blend(_) => Mode::Decay,
+            Mode::Decay => Mode::Off,
+        };
+    }
+}
+
+/// Turns framebuffers into RGB images, hiding the flicker of games that move
+/// sprites by XORing them off and on again.
+pub struct Filter {
+    pub mode: Mode,
+    /// The framebuffer of the previous frame.
+    previous: Vec<u32>,
+    /// The RGB images of the last frames, oldest first.
+    history: VecDeque<Vec<u8>>,
+    /// The RGB image last shown by `decay`, unrounded.
+    glow: Vec<f32>,
+}
+
+impl Filter {
+    pub fn new(mode: Mode) -> Filter {
+        return Filter {
+            mode,
+            previous: Vec::new(),
+            history: VecDeque::new(),
+            glow: Vec::new(),
+        };
+    }
+
+    pub fn set_mode(&mut self, mode: Mode) {
+        self.mode = mode;
+        self.history.clear();
+        self.glow.clear();
+    }
+
+    /// Whether the image keeps changing while the screen stays the same, so
+    /// it has to be rendered every frame.
+    pub fn is_animating(&self) -> bool {
+        return self.mode != Mode::Off;
+    }
+
+    /// The next image to show, 3 bytes per pixel.
+    pub fn apply(&mut self, framebuffer: &Framebuffer, palette: &Palette) -> Vec<u8> {
+        let pixels = &framebuffer.pixels;
+
+        // A resolution switch starts over.
+        if self.previous.len() != pixels.len() {
+            self.previous = pixels.clone();
+            self.history.clear();
+            self.glow.clear();
+        }
+
+        let rgb = match self.mode {
+            Mode::Off => colors(pixels.iter().copied(), palette),
+            Mode::Or => colors(pixels.iter().zip(self.previous.iter()).map(|(a, b)| a | b), palette),
+            Mode::Blend(frames) => {
+                self.history.push_back(colors(pixels.iter().copied(), palette));
+                while self.history.len() > frames {
+                    self.history.pop_front();
+                }
+
+                let count = self.history.len() as u32;
+                (0..pixels.len() * 3)
+                    .map(|i| (self.history.iter().map(|frame| frame[i] as u32).sum::<u32>() / count) as u8)
+                    .collect()
+            }
+            Mode::Decay => {
+                let current = colors(pixels.iter().copied(), palette);
+                let background = colors([0].iter().copied(), palette);
+                if self.glow.len() != current.len() {
+                    self.glow = current.iter().map(|channel| *channel as f32).collect();
+                }
+
+                for (i, channel) in current.iter().enumerate() {
+                    self.glow[i] = if pixels[i / 3] != 0 {
+                        *channel as f32
+                    } else {
+                        let dark = background[i % 3] as f32;
+                        dark + (self.glow[i] - dark) * PERSISTENCE
+                    };
+                }
+                self.glow.iter().map(|channel| channel.round() as u8).collect()
+            }
+        };
+
+        self.previous.clone_from(pixels);
+
+        return rgb;
+    }
+}
+
+fn colors<I: Iterator<Item = u32>>(pixels: I, palette: &Palette) -> Vec<u8> {
+    let mut rgb = Vec::new();
+
+    for pixel in pixels {
+        let color = palette.color(pixel);
+        rgb.extend_from_slice(&[(color >> 16) as u8, (color >> 8) as u8, color as u8]);
+    }
+
+    return rgb;
+}
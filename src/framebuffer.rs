@@ -0,0 +1,119 @@
+pub const ROWS: isize = 32;
+pub const COLUMNS: isize = 64;
+pub const HIRES_ROWS: isize = 64;
+pub const HIRES_COLUMNS: isize = 128;
+pub const PLANES: u32 = 4;
+
+/// The screen as one value per pixel. Each bit of a value is one XO-CHIP
+/// bitplane, so plain CHIP-8 pixels are 0 or 1 and the value doubles as the
+/// palette index.
+pub struct Framebuffer {
+    pub width: isize,
+    pub height: isize,
+    pub hires: bool,
+    pub pixels: Vec<u32>,
+    /// Bitplanes affected by drawing, clearing and scrolling (`FN01`).
+    pub planes: u32,
+    pub dirty: bool,
+}
+
+impl Framebuffer {
+    pub fn new() -> Framebuffer {
+        return Framebuffer {
+            width: COLUMNS,
+            height: ROWS,
+            hires: false,
+            pixels: vec![0; (ROWS * COLUMNS) as usize],
+            planes: 1,
+            dirty: true,
+        };
+    }
+
+    /// Flips the pixel at (x, y) on the given bitplane, wrapping around the
+    /// edges, and returns true when a lit pixel was erased.
+    pub fn set_pixel(&mut self, x: isize, y: isize, plane: u32) -> bool {
+        let x = x.rem_euclid(self.width);
+        let y = y.rem_euclid(self.height);
+        let pixel_loc = (x + y * self.width) as usize;
+
+        self.pixels[pixel_loc] ^= plane;
+        self.dirty = true;
+
+        return self.pixels[pixel_loc] & plane == 0;
+    }
+
+    /// Whether the pixel is lit on any plane.
+    pub fn get_pixel(&self, x: isize, y: isize) -> bool {
+        return self.get_color(x, y) > 0;
+    }
+
+    /// The palette index of the pixel, one bit per plane.
+    pub fn get_color(&self, x: isize, y: isize) -> u32 {
+        return self.pixels[(x + y * self.width) as usize];
+    }
+
+    /// Switches between the 64x32 and the SUPER-CHIP 128x64 mode, which
+    /// clears the screen.
+    pub fn set_hires(&mut self, hires: bool) {
+        self.hires = hires;
+        if hires {
+            self.width = HIRES_COLUMNS;
+            self.height = HIRES_ROWS;
+        } else {
+            self.width = COLUMNS;
+            self.height = ROWS;
+        }
+
+        self.pixels = vec![0; (self.width * self.height) as usize];
+        self.dirty = true;
+    }
+
+    pub fn scroll_down(&mut self, rows: isize) {
+        self.scroll(0, rows);
+    }
+
+    pub fn scroll_up(&mut self, rows: isize) {
+        self.scroll(0, -rows);
+    }
+
+    pub fn scroll_right(&mut self, columns: isize) {
+        self.scroll(columns, 0);
+    }
+
+    pub fn scroll_left(&mut self, columns: isize) {
+        self.scroll(-columns, 0);
+    }
+
+    /// Moves the selected planes by (dx, dy), filling the uncovered area
+    /// with black.
+    fn scroll(&mut self, dx: isize, dy: isize) {
+        let mut scrolled: Vec<u32> = self.pixels.iter().map(|pixel| pixel & !self.planes).collect();
+
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let (from_x, from_y) = (x - dx, y - dy);
+                if from_x >= 0 && from_x < self.width && from_y >= 0 && from_y < self.height {
+                    let moved = self.pixels[(from_x + from_y * self.width) as usize] & self.planes;
+                    scrolled[(x + y * self.width) as usize] |= moved;
+                }
+            }
+        }
+
+        self.pixels = scrolled;
+        self.dirty = true;
+    }
+
+    /// Clears the selected planes.
+    pub fn clear(&mut self) {
+        for pixel in self.pixels.iter_mut() {
+            *pixel &= !self.planes;
+        }
+        self.dirty = true;
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Framebuffer {
+        return Framebuffer::new();
+    }
+}
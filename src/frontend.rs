@@ -0,0 +1,113 @@
+use crate::framebuffer::Framebuffer;
+use crate::keyboard::Keyboard;
+use std::collections::VecDeque;
+
+/// Requests a frontend can make of the emulator besides feeding the keypad.
+pub enum Command {
+    Quit,
+    TogglePause,
+    StepInstruction,
+    StepFrame,
+    /// Writes the numbered quick-save slot.
+    SaveState(u8),
+    /// Restores the numbered quick-save slot.
+    LoadState(u8),
+    /// Starts or stops running backward in time.
+    Rewind(bool),
+    /// Shows which keypad key is being remapped, or the game again once
+    /// remapping is over.
+    Remap(Option<u8>),
+    ToggleFullscreen,
+    /// Switches to the next anti-flicker filter.
+    CycleFilter,
+    /// Saves the screen as a PNG next to the ROM.
+    Screenshot,
+    /// Starts or stops recording a GIF next to the ROM.
+    ToggleCapture,
+    /// The window was uncovered or resized and has to be drawn again.
+    Redraw,
+}
+
+/// Presents the interpreter framebuffer to the user.
+pub trait Display {
+    fn render(&mut self, framebuffer: &Framebuffer) -> Result<(), String>;
+
+    fn toggle_fullscreen(&mut self) -> Result<(), String> {
+        return Ok(());
+    }
+
+    /// Switches to the next display filter and returns its name, if the
+    /// display has filters.
+    fn cycle_filter(&mut self) -> Option<String> {
+        return None;
+    }
+
+    /// Whether the picture changes over time by itself, e.g. while lit
+    /// pixels fade out, so it has to be rendered even when the screen did
+    /// not change.
+    fn is_animating(&self) -> bool {
+        return false;
+    }
+}
+
+/// Feeds host input into the keypad and reports emulator commands. Called
+/// once per pass of the main loop, before the due frames run.
+pub trait Input {
+    fn poll(&mut self, keyboard: &mut Keyboard) -> Vec<Command>;
+}
+
+/// Turns the CHIP-8 beeper on and off.
+pub trait Audio {
+    /// Called once per 60 Hz timer tick.
+    fn set_beeping(&mut self, beeping: bool);
+
+    /// Replaces the square wave by an XO-CHIP 1-bit audio pattern played
+    /// back at the rate given by `pitch`.
+    fn set_pattern(&mut self, _pattern: &[u8], _pitch: u8) {}
+}
+
+/// A frontend that shows nothing, reads no input and stays silent, for
+/// running the core in tests or on machines without a display.
+pub struct Headless;
+
+impl Display for Headless {
+    fn render(&mut self, _framebuffer: &Framebuffer) -> Result<(), String> {
+        return Ok(());
+    }
+}
+
+impl Input for Headless {
+    fn poll(&mut self, _keyboard: &mut Keyboard) -> Vec<Command> {
+        return Vec::new();
+    }
+}
+
+impl Audio for Headless {
+    fn set_beeping(&mut self, _beeping: bool) {}
+}
+
+/// Input that holds down one keypad mask per poll and quits once they run
+/// out, for driving `Chip8::run` from tests.
+pub struct Scripted {
+    pub states: VecDeque<u16>,
+}
+
+impl Scripted {
+    pub fn new(states: &[u16]) -> Scripted {
+        return Scripted {
+            states: states.iter().copied().collect(),
+        };
+    }
+}
+
+impl Input for Scripted {
+    fn poll(&mut self, keyboard: &mut Keyboard) -> Vec<Command> {
+        return match self.states.pop_front() {
+            Some(keys) => {
+                keyboard.set(keys);
+                Vec::new()
+            }
+            None => vec![Command::Quit],
+        };
+    }
+}
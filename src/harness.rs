@@ -0,0 +1,82 @@
+use crate::cpu::Cpu;
+use crate::error::Chip8Error;
+use crate::image;
+use std::fs;
+use std::path::Path;
+
+/// Why `run` returned.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Stop {
+    /// All requested frames were run.
+    Frames,
+    /// The ROM jumped to the jump itself, which is how test ROMs halt once
+    /// their result is on screen.
+    Loop(u16),
+    /// The ROM executed `00FD`.
+    Exited,
+}
+
+/// Runs up to `frames` 60 Hz frames of `cpu.speed` instructions each without
+/// any frontend, and returns how many ran and why it stopped.
+pub fn run(cpu: &mut Cpu, frames: u32) -> Result<(u32, Stop), Chip8Error> {
+    for frame in 0..frames {
+        for _ in 0..cpu.speed {
+            if cpu.exited {
+                return Ok((frame, Stop::Exited));
+            }
+
+            let pc = cpu.pc;
+            let opcode = cpu.read_word(pc)?;
+            cpu.step()?;
+
+            if opcode == 0x1000 | pc && cpu.pc == pc {
+                return Ok((frame + 1, Stop::Loop(pc)));
+            }
+        }
+        cpu.update_timers();
+    }
+
+    return Ok((frames, Stop::Frames));
+}
+
+/// Compares the screen with a golden PBM image. The error shows both screens
+/// side by side, with `#` for lit pixels, so a failure can be read straight
+/// from the test output.
+pub fn compare(cpu: &Cpu, golden: &Path) -> Result<(), String> {
+    let text = fs::read_to_string(golden).map_err(|e| format!("Could not read '{}': {}", golden.display(), e))?;
+    let (width, height, pixels) = image::parse_pbm(&text).map_err(|e| format!("{}: {}", golden.display(), e))?;
+    let framebuffer = &cpu.framebuffer;
+
+    if (width, height) != (framebuffer.width, framebuffer.height) {
+        return Err(format!(
+            "The screen is {}x{} but '{}' is {}x{}",
+            framebuffer.width,
+            framebuffer.height,
+            golden.display(),
+            width,
+            height
+        ));
+    }
+
+    let lit = |set: bool| if set { '#' } else { '.' };
+    let mut report = String::new();
+    let mut differences = 0;
+
+    for y in 0..height {
+        let expected: String = (0..width).map(|x| lit(pixels[(x + y * width) as usize])).collect();
+        let actual: String = (0..width).map(|x| lit(framebuffer.get_pixel(x, y))).collect();
+        differences += expected.chars().zip(actual.chars()).filter(|(a, b)| a != b).count();
+        report.push_str(&format!("{}  {}\n", expected, actual));
+    }
+
+    if differences > 0 {
+        return Err(format!(
+            "{} pixels differ from '{}' (expected left, actual right):\n{}",
+            differences,
+            golden.display(),
+            report
+        ));
+    }
+
+    return Ok(());
+}
@@ -0,0 +1,175 @@
+use crate::framebuffer::Framebuffer;
+use crate::palette::Palette;
+use crate::savestate;
+
+/// The screen as a plain PBM, one line of `0`s and `1`s per row, which keeps
+/// golden images readable in a text editor and in diffs.
+pub fn pbm(framebuffer: &Framebuffer) -> String {
+    let mut out = format!("P1\n{} {}\n", framebuffer.width, framebuffer.height);
+
+    for y in 0..framebuffer.height {
+        for x in 0..framebuffer.width {
+            out.push(if framebuffer.get_pixel(x, y) { '1' } else { '0' });
+        }
+        out.push('\n');
+    }
+
+    return out;
+}
+
+/// Reads a plain PBM into its width, height and lit pixels. `#` comments are
+/// allowed between the fields.
+pub fn parse_pbm(text: &str) -> Result<(isize, isize, Vec<bool>), String> {
+    let mut fields = text
+        .lines()
+        .map(|line| line.split('#').next().unwrap_or(""))
+        .flat_map(|line| line.split_whitespace());
+
+    if fields.next() != Some("P1") {
+        return Err(String::from("Not a plain PBM image"));
+    }
+    let mut size = || -> Result<isize, String> {
+        let field = fields.next().ok_or("PBM image is truncated")?;
+
+        return field.parse().map_err(|_| format!("Invalid PBM size '{}'", field));
+    };
+    let width = size()?;
+    let height = size()?;
+
+    let mut pixels = Vec::new();
+    for field in fields {
+        for bit in field.chars() {
+            match bit {
+                '0' => pixels.push(false),
+                '1' => pixels.push(true),
+                _ => return Err(format!("Invalid PBM pixel '{}'", bit)),
+            }
+        }
+    }
+    if pixels.len() != (width * height) as usize {
+        return Err(format!("PBM image has {} pixels, expected {}", pixels.len(), width * height));
+    }
+
+    return Ok((width, height, pixels));
+}
+
+/// The screen as a PNG, `scale` image pixels wide per CHIP-8 pixel.
+pub fn png(framebuffer: &Framebuffer, palette: &Palette, scale: usize) -> Vec<u8> {
+    let width = framebuffer.width as usize * scale;
+    let height = framebuffer.height as usize * scale;
+
+    return encode_png(width as u32, height as u32, &rgb(framebuffer, palette, scale));
+}
+
+/// The palette indexes of the screen, row by row, with every CHIP-8 pixel
+/// repeated `scale` times in both directions.
+pub fn scaled(framebuffer: &Framebuffer, scale: usize) -> Vec<u32> {
+    let width = framebuffer.width as usize;
+    let mut out = Vec::with_capacity(framebuffer.pixels.len() * scale * scale);
+
+    for row in framebuffer.pixels.chunks(width) {
+        for _ in 0..scale {
+            for pixel in row.iter() {
+                for _ in 0..scale {
+                    out.push(*pixel);
+                }
+            }
+        }
+    }
+
+    return out;
+}
+
+/// The screen as 8-bit RGB pixels, scaled like `scaled`.
+pub fn rgb(framebuffer: &Framebuffer, palette: &Palette, scale: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    for pixel in scaled(framebuffer, scale) {
+        let color = palette.color(pixel);
+        out.extend_from_slice(&[(color >> 16) as u8, (color >> 8) as u8, color as u8]);
+    }
+
+    return out;
+}
+
+/// Identifies a screen in a few bytes, for checks where storing a whole
+/// golden image is not worth it.
+pub fn hash(framebuffer: &Framebuffer) -> u64 {
+    let mut data = vec![framebuffer.hires as u8];
+    data.extend(framebuffer.pixels.iter().map(|pixel| *pixel as u8));
+
+    return savestate::rom_hash(&data);
+}
+
+/// Encodes 8-bit RGB pixels as a PNG. The image data is stored without
+/// compression, which keeps the encoder tiny and is fine at CHIP-8 sizes.
+pub fn encode_png(width: u32, height: u32, rgb: &[u8]) -> Vec<u8> {
+    let mut out = b"\x89PNG\r\n\x1a\n".to_vec();
+
+    let mut header = Vec::new();
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // 8 bits per channel, RGB, deflate, adaptive filtering, no interlace.
+    header.extend_from_slice(&[8, 2, 0, 0, 0]);
+    chunk(&mut out, b"IHDR", &header);
+
+    // Every row starts with filter type 0, none.
+    let mut raw = Vec::new();
+    for row in rgb.chunks((width * 3) as usize) {
+        raw.push(0);
+        raw.extend_from_slice(row);
+    }
+
+    let mut zlib = vec![0x78, 0x01];
+    let mut blocks = raw.chunks(0xFFFF).peekable();
+    if blocks.peek().is_none() {
+        zlib.extend_from_slice(&[1, 0, 0, 0xFF, 0xFF]);
+    }
+    while let Some(block) = blocks.next() {
+        zlib.push(blocks.peek().is_none() as u8);
+        zlib.extend_from_slice(&(block.len() as u16).to_le_bytes());
+        zlib.extend_from_slice(&(!(block.len() as u16)).to_le_bytes());
+        zlib.extend_from_slice(block);
+    }
+    zlib.extend_from_slice(&adler32(&raw).to_be_bytes());
+    chunk(&mut out, b"IDAT", &zlib);
+
+    chunk(&mut out, b"IEND", &[]);
+
+    return out;
+}
+
+fn chunk(out: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    out.extend_from_slice(&(data.len() as u32).to_be_bytes());
+
+    let start = out.len();
+    out.extend_from_slice(kind);
+    out.extend_from_slice(data);
+    let crc = crc32(&out[start..]);
+
+    out.extend_from_slice(&crc.to_be_bytes());
+}
+
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFF;
+
+    for byte in data.iter() {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 != 0 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 };
+        }
+    }
+
+    return !crc;
+}
+
+fn adler32(data: &[u8]) -> u32 {
+    let (mut a, mut b) = (1u32, 0u32);
+
+    for byte in data.iter() {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+
+    return (b << 16) | a;
+}
@@ -1,59 +1,119 @@
-use sdl2::keyboard::Keycode;
+use std::collections::HashMap;
 
+/// The 16-key hex keypad. Bit N of each mask stands for key N.
 pub struct Keyboard {
-    pub keys_pressed: Vec<u8>,
-    pub last_key_pressed: u8,
+    /// Keys that are down.
+    pub held: u16,
+    /// Keys that went down since the current 60 Hz frame or key wait began.
+    pub pressed: u16,
+    /// Keys that went up since the current 60 Hz frame or key wait began.
+    pub released: u16,
 }
 
 impl Keyboard {
     pub fn new() -> Keyboard {
         return Keyboard {
-            keys_pressed: Vec::new(),
-            last_key_pressed: 0,
+            held: 0,
+            pressed: 0,
+            released: 0,
         };
     }
 
-    pub fn is_key_pressed(&mut self, keycode: u8) -> u8 {
-        let is_pressed = self.keys_pressed.iter().find(|&key| key == &keycode);
+    pub fn is_key_pressed(&self, key: u8) -> bool {
+        return self.held & bit(key) != 0;
+    }
 
-        return *is_pressed.unwrap_or(&0);
+    /// The held keys.
+    pub fn mask(&self) -> u16 {
+        return self.held;
     }
 
-    pub fn on_key_down(&mut self, keycode: Keycode) {
-        let key = self.get_pressed(keycode);
-        self.keys_pressed.push(key);
-        self.last_key_pressed = key;
+    /// Presses a key. A key that is already down, as with OS key repeat,
+    /// stays down without a new edge.
+    pub fn on_key_down(&mut self, key: u8) {
+        self.set(self.held | bit(key));
     }
 
-    pub fn on_key_up(&mut self, keycode: Keycode) {
-        let key = self.get_pressed(keycode);
-        if let Some(pos) = self.keys_pressed.iter().position(|x| *x == key) {
-            self.keys_pressed.remove(pos);
-        }
-        self.last_key_pressed = 0;
-    }
-
-    pub fn get_pressed(&self, keycode: Keycode) -> u8 {
-        match keycode {
-            Keycode::Num1 => 0x1,
-            Keycode::Num2 => 0x2,
-            Keycode::Num3 => 0x3,
-            Keycode::Num4 => 0xc,
-            Keycode::Q => 0x4,
-            Keycode::W => 0x5,
-            Keycode::E => 0x6,
-            Keycode::R => 0xD,
-            Keycode::A => 0x7,
-            Keycode::S => 0x8,
-            Keycode::D => 0x9,
-            Keycode::F => 0xE,
-            Keycode::Z => 0xA,
-            Keycode::X => 0x0,
-            Keycode::C => 0xB,
-            Keycode::V => 0xF,
-            Keycode::Escape => 0xFF,
-            _ => 0x10,
+    pub fn on_key_up(&mut self, key: u8) {
+        self.set(self.held & !bit(key));
+    }
+
+    /// Replaces the held keys all at once, e.g. from a movie, recording the
+    /// keys that changed as edges.
+    pub fn set(&mut self, held: u16) {
+        self.pressed |= held & !self.held;
+        self.released |= self.held & !held;
+        self.held = held;
+    }
+
+    /// Forgets the edges; called at the end of every 60 Hz frame and when
+    /// `FX0A` starts waiting.
+    pub fn clear_edges(&mut self) {
+        self.pressed = 0;
+        self.released = 0;
+    }
+}
+
+impl Default for Keyboard {
+    fn default() -> Keyboard {
+        return Keyboard::new();
+    }
+}
+
+/// A host device keypad keys are held on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    Keyboard,
+    /// A game controller by SDL instance id.
+    Gamepad(u32),
+}
+
+/// The keypad keys held on each host device, so letting go of a key on one
+/// device keeps it down while another still holds it.
+pub struct HostKeys {
+    keyboard: u16,
+    gamepads: HashMap<u32, u16>,
+}
+
+impl HostKeys {
+    pub fn new() -> HostKeys {
+        return HostKeys {
+            keyboard: 0,
+            gamepads: HashMap::new(),
+        };
+    }
+
+    pub fn set(&mut self, source: Source, key: u8, down: bool) {
+        let keys = match source {
+            Source::Keyboard => &mut self.keyboard,
+            Source::Gamepad(id) => self.gamepads.entry(id).or_insert(0),
+        };
+
+        if down {
+            *keys |= bit(key);
+        } else {
+            *keys &= !bit(key);
         }
     }
-    
-}
\ No newline at end of file
+
+    /// Forgets an unplugged controller, letting go of the keys it held.
+    pub fn remove_gamepad(&mut self, id: u32) {
+        self.gamepads.remove(&id);
+    }
+
+    /// The keys held on any device.
+    pub fn held(&self) -> u16 {
+        return self.gamepads.values().fold(self.keyboard, |held, keys| held | keys);
+    }
+}
+
+impl Default for HostKeys {
+    fn default() -> HostKeys {
+        return HostKeys::new();
+    }
+}
+
+/// Keys past 0xF alias the low nibble, as `EX9E` only looks at that.
+fn bit(key: u8) -> u16 {
+    return 1 << (key & 0xF);
+}
@@ -0,0 +1,252 @@
+use crate::config::Config;
+use std::collections::HashMap;
+
+/// What a host key does.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Action {
+    /// Holds down one of the 16 CHIP-8 keys.
+    Key(u8),
+    Quit,
+    Pause,
+    StepInstruction,
+    StepFrame,
+    /// Runs backward while held.
+    Rewind,
+    /// Writes the numbered quick-save slot.
+    Save(u8),
+    /// Restores the numbered quick-save slot.
+    Load(u8),
+    /// Opens the screen for assigning new keys to the keypad.
+    Remap,
+    Fullscreen,
+    /// Switches to the next anti-flicker filter.
+    Filter,
+    Screenshot,
+    /// Starts or stops recording a GIF.
+    Capture,
+}
+
+/// Host keys as named by SDL, e.g. `X`, `F1` or `Backspace`, optionally
+/// prefixed with `Shift+`, or game controller buttons such as `dpup` or `a`,
+/// and the actions they trigger.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Keymap {
+    bindings: HashMap<String, Action>,
+}
+
+/// The COSMAC VIP keypad on the left of a QWERTY keyboard:
+///
+/// ```text
+/// 1 2 3 C      1 2 3 4
+/// 4 5 6 D  ->  Q W E R
+/// 7 8 9 E      A S D F
+/// A 0 B F      Z X C V
+/// ```
+const DEFAULT_KEYS: [&str; 16] = ["X", "1", "2", "3", "Q", "W", "E", "A", "S", "D", "Z", "C", "4", "R", "F", "V"];
+
+impl Keymap {
+    pub fn new() -> Keymap {
+        let mut keymap = Keymap {
+            bindings: HashMap::new(),
+        };
+
+        for (key, name) in DEFAULT_KEYS.iter().enumerate() {
+            keymap.bind(Action::Key(key as u8), &[name]);
+        }
+        keymap.bind(Action::Quit, &["Escape"]);
+        keymap.bind(Action::Pause, &["P", "F5"]);
+        keymap.bind(Action::StepInstruction, &["F6"]);
+        keymap.bind(Action::StepFrame, &["F7"]);
+        keymap.bind(Action::Remap, &["F8"]);
+        keymap.bind(Action::Filter, &["F9"]);
+        keymap.bind(Action::Capture, &["F10"]);
+        keymap.bind(Action::Fullscreen, &["F11"]);
+        keymap.bind(Action::Screenshot, &["F12"]);
+        keymap.bind(Action::Rewind, &["Backspace"]);
+        for slot in 1..=4 {
+            keymap.bind(Action::Load(slot), &[&format!("F{}", slot)]);
+            keymap.bind(Action::Save(slot), &[&format!("Shift+F{}", slot)]);
+        }
+
+        return keymap;
+    }
+
+    /// Game controller buttons: the D-pad on 2/4/6/8, which most games use
+    /// for directions, A on 5 and B on A.
+    pub fn gamepad() -> Keymap {
+        let mut keymap = Keymap {
+            bindings: HashMap::new(),
+        };
+
+        keymap.bind(Action::Key(0x2), &["dpup"]);
+        keymap.bind(Action::Key(0x4), &["dpleft"]);
+        keymap.bind(Action::Key(0x6), &["dpright"]);
+        keymap.bind(Action::Key(0x8), &["dpdown"]);
+        keymap.bind(Action::Key(0x5), &["a"]);
+        keymap.bind(Action::Key(0xA), &["b"]);
+        keymap.bind(Action::Pause, &["start"]);
+        keymap.bind(Action::Rewind, &["back"]);
+
+        return keymap;
+    }
+
+    /// Applies the `[keys]` section, `key0` to `keyf`, and the `[hotkeys]`
+    /// section over the defaults. Each entry replaces the default keys of its
+    /// action and may list several keys separated by commas.
+    pub fn from_config(config: &Config) -> Result<Keymap, String> {
+        let mut keymap = Keymap::new();
+
+        keymap.apply(config, "keys", |action| matches!(action, Action::Key(_)))?;
+        keymap.apply(config, "hotkeys", |action| !matches!(action, Action::Key(_)))?;
+
+        return Ok(keymap);
+    }
+
+    /// The buttons of the controller called `name`: the `[gamepad]` section
+    /// over the defaults, then the controller's own profile, e.g.
+    /// `[gamepad.xbox 360 controller]`. Both take keypad keys and hotkeys.
+    pub fn gamepad_from_config(config: &Config, name: &str) -> Result<Keymap, String> {
+        let mut keymap = Keymap::gamepad();
+
+        keymap.apply(config, "gamepad", |_| true)?;
+        keymap.apply(config, &format!("gamepad.{}", name.trim().to_lowercase()), |_| true)?;
+
+        return Ok(keymap);
+    }
+
+    fn apply(&mut self, config: &Config, section: &str, allowed: fn(&Action) -> bool) -> Result<(), String> {
+        for (name, value) in config.section(section) {
+            let action = Keymap::parse_action(name)
+                .filter(allowed)
+                .ok_or(format!("Unknown entry '{}' in [{}]", name, section))?;
+            let keys: Vec<&str> = value.split(',').map(|key| key.trim()).filter(|key| !key.is_empty()).collect();
+
+            self.bind(action, &keys);
+        }
+
+        return Ok(());
+    }
+
+    /// The config name of an action, as used by `from_config`.
+    pub fn action_name(action: Action) -> String {
+        return match action {
+            Action::Key(key) => format!("key{:x}", key),
+            Action::Quit => String::from("quit"),
+            Action::Pause => String::from("pause"),
+            Action::StepInstruction => String::from("step"),
+            Action::StepFrame => String::from("frame"),
+            Action::Rewind => String::from("rewind"),
+            Action::Save(slot) => format!("save{}", slot),
+            Action::Load(slot) => format!("load{}", slot),
+            Action::Remap => String::from("remap"),
+            Action::Fullscreen => String::from("fullscreen"),
+            Action::Filter => String::from("filter"),
+            Action::Screenshot => String::from("screenshot"),
+            Action::Capture => String::from("capture"),
+        };
+    }
+
+    pub fn parse_action(name: &str) -> Option<Action> {
+        let name = name.to_lowercase();
+        let slot = |prefix: &str| {
+            name.strip_prefix(prefix)
+                .and_then(|slot| slot.parse::<u8>().ok())
+                .filter(|slot| (1..=4).contains(slot))
+        };
+
+        if let Some(key) = name.strip_prefix("key") {
+            return u8::from_str_radix(key, 16).ok().filter(|_| key.len() == 1).map(Action::Key);
+        }
+        if let Some(slot) = slot("save") {
+            return Some(Action::Save(slot));
+        }
+        if let Some(slot) = slot("load") {
+            return Some(Action::Load(slot));
+        }
+
+        return match name.as_str() {
+            "quit" => Some(Action::Quit),
+            "pause" => Some(Action::Pause),
+            "step" => Some(Action::StepInstruction),
+            "frame" => Some(Action::StepFrame),
+            "rewind" => Some(Action::Rewind),
+            "remap" => Some(Action::Remap),
+            "fullscreen" => Some(Action::Fullscreen),
+            "filter" => Some(Action::Filter),
+            "screenshot" => Some(Action::Screenshot),
+            "capture" => Some(Action::Capture),
+            _ => None,
+        };
+    }
+
+    /// Makes `keys` the only keys of `action`, taking them away from any
+    /// other action they were bound to.
+    pub fn bind(&mut self, action: Action, keys: &[&str]) {
+        self.bindings.retain(|_, bound| *bound != action);
+
+        for key in keys.iter() {
+            self.bindings.insert(normalize(key), action);
+        }
+    }
+
+    /// What the key does, preferring a `Shift+` binding while Shift is held.
+    pub fn action(&self, key: &str, shift: bool) -> Option<Action> {
+        let key = normalize(key);
+
+        if shift {
+            if let Some(action) = self.bindings.get(&format!("shift+{}", key)) {
+                return Some(*action);
+            }
+        }
+
+        return self.bindings.get(&key).copied();
+    }
+
+    /// The keys bound to an action, lowercased and sorted.
+    pub fn keys(&self, action: Action) -> Vec<String> {
+        let mut keys: Vec<String> = self
+            .bindings
+            .iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(key, _)| key.clone())
+            .collect();
+        keys.sort();
+
+        return keys;
+    }
+
+    /// Every bound key without its `shift+` prefix, for checking the names
+    /// against the host's key names.
+    pub fn key_names(&self) -> Vec<&str> {
+        return self
+            .bindings
+            .keys()
+            .map(|key| key.strip_prefix("shift+").unwrap_or(key))
+            .collect();
+    }
+
+    /// The keypad bindings as a `[keys]` section.
+    pub fn keypad_entries(&self) -> Vec<(String, String)> {
+        return (0..16)
+            .map(|key| {
+                let action = Action::Key(key);
+                (Keymap::action_name(action), self.keys(action).join(", "))
+            })
+            .collect();
+    }
+}
+
+impl Default for Keymap {
+    fn default() -> Keymap {
+        return Keymap::new();
+    }
+}
+
+fn normalize(key: &str) -> String {
+    let key = key.trim().to_lowercase();
+
+    return match key.strip_prefix("shift+") {
+        Some(rest) => format!("shift+{}", rest.trim()),
+        None => key,
+    };
+}
@@ -0,0 +1,31 @@
+pub mod assembler;
+pub mod capture;
+pub mod chip8;
+pub mod config;
+pub mod debugger;
+pub mod disasm;
+pub mod error;
+pub mod filter;
+pub mod cpu;
+pub mod framebuffer;
+pub mod frontend;
+pub mod harness;
+pub mod image;
+pub mod keyboard;
+pub mod keymap;
+pub mod movie;
+pub mod opcodes;
+pub mod palette;
+pub mod quirks;
+pub mod rewind;
+pub mod rom;
+pub mod rng;
+pub mod savestate;
+pub mod scheduler;
+pub mod speaker;
+pub mod terminal;
+
+#[cfg(feature = "sdl")]
+pub mod renderer;
+#[cfg(feature = "sdl")]
+pub mod sdl_input;
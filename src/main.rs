@@ -1,18 +1,257 @@
-mod chip8;
-mod cpu;
-mod keyboard;
-mod renderer;
-
-use chip8::Chip8;
-use cpu::Cpu;
-use keyboard::Keyboard;
-use renderer::Renderer;
-
-pub fn main() -> Result<(), String> {
-    let keyboard = Keyboard::new();
-    let renderer = Renderer::new(12, keyboard)?;
-
-    let cpu = Cpu::new(renderer);
-    let mut chip8 = Chip8::new(cpu);
-    return chip8.run();
+mod options;
+
+use options::{HeadlessOptions, Options, ToolOptions};
+use rust_chip::assembler;
+use rust_chip::capture::{self, Settings};
+use rust_chip::chip8::Chip8;
+use rust_chip::config::Config;
+use rust_chip::cpu::Cpu;
+use rust_chip::debugger::{self, Breakpoint};
+use rust_chip::disasm;
+use rust_chip::frontend::{Audio, Display, Headless, Input};
+use rust_chip::harness::{self, Stop};
+use rust_chip::image;
+use rust_chip::palette::Palette;
+use rust_chip::quirks::Quirks;
+#[cfg(feature = "sdl")]
+use rust_chip::renderer::Renderer;
+use rust_chip::movie::Movie;
+use rust_chip::rewind::Rewind;
+use rust_chip::rng::Rng;
+use rust_chip::rom;
+use rust_chip::savestate;
+use rust_chip::scheduler::ClockRate;
+#[cfg(feature = "sdl")]
+use rust_chip::sdl_input::SdlInput;
+#[cfg(feature = "sdl")]
+use rust_chip::speaker::Speaker;
+use rust_chip::speaker::WavSpeaker;
+use rust_chip::terminal::{Bell, TerminalDisplay, TerminalInput};
+use std::fs;
+use std::path::PathBuf;
+use std::process;
+
+pub fn main() {
+    if let Err(message) = run() {
+        eprintln!("{}", message);
+        process::exit(1);
+    }
+}
+
+fn run() -> Result<(), String> {
+    let mut args = std::env::args().skip(1).peekable();
+
+    if args.peek().map(|arg| arg.as_str()) == Some("disasm") {
+        args.next();
+        return disassemble(ToolOptions::parse_disasm(args)?);
+    }
+    if args.peek().map(|arg| arg.as_str()) == Some("assemble") {
+        args.next();
+        return assemble(ToolOptions::parse_assemble(args)?);
+    }
+    if args.peek().map(|arg| arg.as_str()) == Some("headless") {
+        args.next();
+        return headless(HeadlessOptions::parse(args)?);
+    }
+
+    let options = Options::parse(args)?;
+
+    let config_path = options.config.clone().or_else(Config::default_path);
+    let mut config = match &config_path {
+        Some(path) => Config::load(path)?,
+        None => Config::new(),
+    };
+    config.merge(Config::load(&Config::rom_path(&options.rom))?);
+
+    let quirks = match &options.quirks {
+        Some(name) => Quirks::from_name(name)?,
+        None => Quirks::from_config(&config)?,
+    };
+
+    let palette = palette(options.theme.as_deref(), options.palette.as_deref(), &config)?;
+
+    let (display, input, audio) = match options.terminal {
+        true => terminal_frontend(&options, &config, &palette)?,
+        false => window_frontend(&options, &config, config_path, &palette)?,
+    };
+
+    let mut cpu = Cpu::new();
+    cpu.speed = options.speed;
+    cpu.paused = options.paused;
+    cpu.quirks = quirks;
+    cpu.set_stack_depth(options.stack);
+    cpu.load_address = options.address;
+
+    let mut chip8 = Chip8::new(cpu, &options.rom, display, input, audio)?;
+    if let Some(hz) = options.hz {
+        chip8.scheduler.rate = ClockRate::Hertz(hz);
+    }
+
+    chip8.rewind = Rewind::new(options.rewind << 20);
+    chip8.capture = Settings {
+        palette,
+        scale: options.capture_scale,
+        audio: options.capture_audio,
+    };
+    if let Some(seed) = options.seed {
+        chip8.cpu.rng = Rng::new(seed);
+    }
+    if let Some(path) = &options.play {
+        chip8.play_movie(Movie::load_file(path)?)?;
+    }
+    if options.record.is_some() {
+        chip8.record_movie();
+    }
+    if let Some(path) = &options.state {
+        savestate::load_file(&mut chip8.cpu, path)?;
+    }
+
+    for address in options.breakpoints {
+        chip8.debugger.add(Breakpoint::Address(address));
+    }
+    if options.debug {
+        chip8.console = Some(debugger::spawn_console());
+    }
+
+    if let Some(path) = &options.capture {
+        chip8.start_capture(path)?;
+    }
+
+    chip8.run()?;
+
+    chip8.stop_capture()?;
+    if let Some(path) = &options.screenshot {
+        capture::save_screenshot(&chip8.cpu.framebuffer, &chip8.capture, path)?;
+    }
+
+    if let (Some(path), Some(movie)) = (&options.record, &chip8.recording) {
+        movie.save_file(path)?;
+        println!("Recorded {} frames to {}", movie.frames.len(), path.display());
+    }
+
+    return Ok(());
+}
+
+/// The display, input and audio of a run.
+type Frontend = (Box<dyn Display>, Box<dyn Input>, Box<dyn Audio>);
+
+fn terminal_frontend(options: &Options, config: &Config, palette: &Palette) -> Result<Frontend, String> {
+    let input = TerminalInput::new(config)?;
+    let display = TerminalDisplay::new(palette.clone(), options.filter, options.braille);
+    let audio: Box<dyn Audio> = if let Some(path) = &options.wav {
+        Box::new(WavSpeaker::new(path)?)
+    } else if options.mute {
+        Box::new(Headless)
+    } else {
+        Box::new(Bell::new())
+    };
+
+    return Ok((Box::new(display), Box::new(input), audio));
+}
+
+#[cfg(feature = "sdl")]
+fn window_frontend(options: &Options, config: &Config, config_path: Option<PathBuf>, palette: &Palette) -> Result<Frontend, String> {
+    let sdl_context = sdl2::init()?;
+    let renderer = Renderer::new(
+        &sdl_context,
+        options.scale,
+        palette.clone(),
+        options.filter,
+        options.fullscreen,
+        options.integer_scale,
+    )?;
+    let input = SdlInput::new(&sdl_context, config, config_path)?;
+    let audio: Box<dyn Audio> = if let Some(path) = &options.wav {
+        Box::new(WavSpeaker::new(path)?)
+    } else if options.mute {
+        Box::new(Headless)
+    } else {
+        match Speaker::new(&sdl_context) {
+            Ok(speaker) => Box::new(speaker),
+            Err(e) => {
+                eprintln!("No audio device available, running without sound: {}", e);
+                Box::new(Headless)
+            }
+        }
+    };
+
+    return Ok((Box::new(renderer), Box::new(input), audio));
+}
+
+#[cfg(not(feature = "sdl"))]
+fn window_frontend(_options: &Options, _config: &Config, _config_path: Option<PathBuf>, _palette: &Palette) -> Result<Frontend, String> {
+    return Err(String::from("This build has no SDL window, use --terminal"));
+}
+
+fn disassemble(options: ToolOptions) -> Result<(), String> {
+    let rom = rom::read(&options.input)?;
+    let listing = disasm::disassemble(&rom, options.address).to_string();
+
+    return match &options.output {
+        Some(path) => fs::write(path, listing).map_err(|e| format!("Could not write '{}': {}", path.display(), e)),
+        None => {
+            print!("{}", listing);
+            Ok(())
+        }
+    };
+}
+
+/// The theme given on the command line or else the configured palette, with
+/// any `--palette` colors on top.
+fn palette(theme: Option<&str>, colors: Option<&str>, config: &Config) -> Result<Palette, String> {
+    let mut palette = match theme {
+        Some(name) => Palette::from_name(name)?,
+        None => Palette::from_config(config)?,
+    };
+    if let Some(colors) = colors {
+        palette.override_colors(colors)?;
+    }
+
+    return Ok(palette);
+}
+
+fn headless(options: HeadlessOptions) -> Result<(), String> {
+    let mut cpu = Cpu::new();
+    cpu.speed = options.speed;
+    cpu.load_address = options.address;
+    if let Some(name) = &options.quirks {
+        cpu.quirks = Quirks::from_name(name)?;
+    }
+    cpu.load_sprites();
+    cpu.load_program(&options.rom)?;
+
+    let (frames, stop) = harness::run(&mut cpu, options.frames).map_err(|e| e.to_string())?;
+    match stop {
+        Stop::Frames => println!("Ran {} frames", frames),
+        Stop::Loop(pc) => println!("Halted at 0x{:03X} after {} frames", pc, frames),
+        Stop::Exited => println!("Exited after {} frames", frames),
+    }
+
+    if let Some(path) = &options.pbm {
+        fs::write(path, image::pbm(&cpu.framebuffer)).map_err(|e| format!("Could not write '{}': {}", path.display(), e))?;
+    }
+    if let Some(path) = &options.png {
+        let palette = palette(options.theme.as_deref(), options.palette.as_deref(), &Config::new())?;
+        let png = image::png(&cpu.framebuffer, &palette, options.scale);
+        fs::write(path, png).map_err(|e| format!("Could not write '{}': {}", path.display(), e))?;
+    }
+    if options.hash {
+        println!("{:016x}", image::hash(&cpu.framebuffer));
+    }
+    if let Some(path) = &options.golden {
+        harness::compare(&cpu, path)?;
+        println!("The screen matches {}", path.display());
+    }
+
+    return Ok(());
+}
+
+fn assemble(options: ToolOptions) -> Result<(), String> {
+    let rom = assembler::assemble_file(&options.input, options.address)?;
+    let path = match options.output {
+        Some(path) => path,
+        None => options.input.with_extension("ch8"),
+    };
+
+    return fs::write(&path, rom).map_err(|e| format!("Could not write '{}': {}", path.display(), e));
 }
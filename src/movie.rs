@@ -0,0 +1,171 @@
+use crate::cpu::Cpu;
+use crate::keyboard::Keyboard;
+use crate::savestate::{self, quirk_flags, quirks_from_flags, Reader};
+use crate::quirks::Quirks;
+use crate::rng::Rng;
+use crate::scheduler::ClockRate;
+use std::fs;
+use std::path::Path;
+
+/// The first bytes of every movie file.
+const MAGIC: &[u8; 8] = b"CHIP8MOV";
+
+/// Bumped whenever the layout changes.
+pub const VERSION: u16 = 3;
+
+/// The keypad as seen by one frame, edges included, so taps that go down
+/// and up between two polls replay too.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Frame {
+    /// Bit N is set while key N is held.
+    pub keys: u16,
+    pub pressed: u16,
+    pub released: u16,
+}
+
+impl Frame {
+    pub fn from_keyboard(keyboard: &Keyboard) -> Frame {
+        return Frame {
+            keys: keyboard.held,
+            pressed: keyboard.pressed,
+            released: keyboard.released,
+        };
+    }
+
+    pub fn apply(&self, keyboard: &mut Keyboard) {
+        keyboard.held = self.keys;
+        keyboard.pressed = self.pressed;
+        keyboard.released = self.released;
+    }
+}
+
+/// Everything needed to replay a run from power-on: the machine it ran on
+/// and the keypad of every frame.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Movie {
+    pub rom_hash: u64,
+    pub seed: u64,
+    pub quirks: Quirks,
+    pub rate: ClockRate,
+    pub stack_depth: usize,
+    pub load_address: u16,
+    pub frames: Vec<Frame>,
+}
+
+impl Movie {
+    /// Starts recording a CPU that has just loaded its ROM.
+    pub fn new(cpu: &Cpu, rate: ClockRate) -> Movie {
+        return Movie {
+            rom_hash: cpu.rom_hash,
+            seed: cpu.rng.state,
+            quirks: cpu.quirks,
+            rate,
+            stack_depth: cpu.stack.len(),
+            load_address: cpu.load_address,
+            frames: Vec::new(),
+        };
+    }
+
+    /// Puts a CPU that has just loaded `rom` into the recorded starting
+    /// state, and returns the clock rate the movie was recorded at.
+    pub fn start(&self, cpu: &mut Cpu, rom: &[u8]) -> Result<ClockRate, String> {
+        if self.rom_hash != savestate::rom_hash(rom) {
+            return Err(String::from("The movie was recorded with a different ROM"));
+        }
+
+        // The quirks decide the memory size, so the ROM is loaded again.
+        cpu.quirks = self.quirks;
+        cpu.set_stack_depth(self.stack_depth);
+        cpu.load_address = self.load_address;
+        cpu.load_rom(rom)?;
+        cpu.rng = Rng::new(self.seed);
+
+        return Ok(self.rate);
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+
+        out.extend_from_slice(MAGIC);
+        out.extend_from_slice(&VERSION.to_le_bytes());
+        out.extend_from_slice(&self.rom_hash.to_le_bytes());
+        out.extend_from_slice(&self.seed.to_le_bytes());
+        out.push(quirk_flags(&self.quirks));
+        let (kind, value) = match self.rate {
+            ClockRate::PerFrame(count) => (0, count),
+            ClockRate::Hertz(hz) => (1, hz),
+        };
+        out.push(kind);
+        out.extend_from_slice(&value.to_le_bytes());
+        out.push(self.stack_depth as u8);
+        out.extend_from_slice(&self.load_address.to_le_bytes());
+
+        out.extend_from_slice(&(self.frames.len() as u32).to_le_bytes());
+        for frame in self.frames.iter() {
+            out.extend_from_slice(&frame.keys.to_le_bytes());
+            out.extend_from_slice(&frame.pressed.to_le_bytes());
+            out.extend_from_slice(&frame.released.to_le_bytes());
+        }
+
+        return out;
+    }
+
+    pub fn from_bytes(data: &[u8]) -> Result<Movie, String> {
+        let mut reader = Reader { data, position: 0 };
+
+        if reader.bytes(MAGIC.len())? != MAGIC {
+            return Err(String::from("Not a movie file"));
+        }
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(format!("Unsupported movie version {}, expected {}", version, VERSION));
+        }
+
+        let rom_hash = reader.u64()?;
+        let seed = reader.u64()?;
+        let quirks = quirks_from_flags(reader.u8()?);
+        let rate = match (reader.u8()?, reader.u32()?) {
+            (0, count) => ClockRate::PerFrame(count),
+            (1, hz) => ClockRate::Hertz(hz),
+            (kind, _) => return Err(format!("Unknown clock rate kind {}", kind)),
+        };
+        let stack_depth = match reader.u8()? {
+            0 => return Err(String::from("Movie has a stack depth of 0")),
+            depth => depth as usize,
+        };
+        let load_address = reader.u16()?;
+
+        let count = reader.u32()? as usize;
+        let mut frames = Vec::new();
+        for _ in 0..count {
+            frames.push(Frame {
+                keys: reader.u16()?,
+                pressed: reader.u16()?,
+                released: reader.u16()?,
+            });
+        }
+        if reader.position != data.len() {
+            return Err(String::from("Movie has trailing data"));
+        }
+
+        return Ok(Movie {
+            rom_hash,
+            seed,
+            quirks,
+            rate,
+            stack_depth,
+            load_address,
+            frames,
+        });
+    }
+
+    pub fn save_file(&self, path: &Path) -> Result<(), String> {
+        return fs::write(path, self.to_bytes()).map_err(|e| format!("Could not write '{}': {}", path.display(), e));
+    }
+
+    pub fn load_file(path: &Path) -> Result<Movie, String> {
+        let data = fs::read(path).map_err(|e| format!("Could not read '{}': {}", path.display(), e))?;
+
+        return Movie::from_bytes(&data).map_err(|e| format!("{}: {}", path.display(), e));
+    }
+}
@@ -0,0 +1,188 @@
+/// Every instruction understood by the interpreter, including the SUPER-CHIP
+/// and XO-CHIP extensions.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Op {
+    Clear,
+    Return,
+    ScrollDown,
+    ScrollUp,
+    ScrollRight,
+    ScrollLeft,
+    Exit,
+    LowResolution,
+    HighResolution,
+    Jump,
+    Call,
+    SkipEqualByte,
+    SkipNotEqualByte,
+    SkipEqual,
+    SaveRange,
+    LoadRange,
+    LoadByte,
+    AddByte,
+    Move,
+    Or,
+    And,
+    Xor,
+    Add,
+    Sub,
+    ShiftRight,
+    ReverseSub,
+    ShiftLeft,
+    SkipNotEqual,
+    LoadI,
+    JumpOffset,
+    Random,
+    Draw,
+    SkipPressed,
+    SkipNotPressed,
+    LoadLongI,
+    SelectPlanes,
+    LoadAudio,
+    LoadDelay,
+    WaitKey,
+    SetDelay,
+    SetSound,
+    AddI,
+    LoadFont,
+    LoadBigFont,
+    SetPitch,
+    Bcd,
+    Store,
+    Load,
+    StoreFlags,
+    LoadFlags,
+}
+
+/// One row of the opcode table.
+///
+/// `syntax` is the assembly form, where these placeholders stand for the
+/// operand fields of the opcode:
+///
+/// * `Vx`, `Vy`: the register in the second or third nibble
+/// * `nnn`, `nn`, `n`: the lowest 12, 8 or 4 bits
+/// * `x`: the second nibble as a plain number
+/// * `nnnn`: the word following the opcode
+pub struct Opcode {
+    pub op: Op,
+    pub pattern: u16,
+    pub mask: u16,
+    pub syntax: &'static str,
+    pub name: &'static str,
+}
+
+impl Opcode {
+    /// Size in bytes, including the trailing word of `F000 NNNN`.
+    pub fn size(&self) -> u16 {
+        if self.syntax.contains("nnnn") {
+            return 4;
+        }
+
+        return 2;
+    }
+}
+
+const fn opcode(op: Op, pattern: u16, mask: u16, syntax: &'static str, name: &'static str) -> Opcode {
+    return Opcode {
+        op,
+        pattern,
+        mask,
+        syntax,
+        name,
+    };
+}
+
+/// Searched in order, so exact opcodes come before the ones they overlap.
+pub const OPCODES: [Opcode; 50] = [
+    opcode(Op::Clear, 0x00E0, 0xFFFF, "CLS", "Clear"),
+    opcode(Op::Return, 0x00EE, 0xFFFF, "RET", "Return"),
+    opcode(Op::ScrollDown, 0x00C0, 0xFFF0, "SCD n", "Scroll down"),
+    opcode(Op::ScrollUp, 0x00D0, 0xFFF0, "SCU n", "Scroll up"),
+    opcode(Op::ScrollRight, 0x00FB, 0xFFFF, "SCR", "Scroll right"),
+    opcode(Op::ScrollLeft, 0x00FC, 0xFFFF, "SCL", "Scroll left"),
+    opcode(Op::Exit, 0x00FD, 0xFFFF, "EXIT", "Exit"),
+    opcode(Op::LowResolution, 0x00FE, 0xFFFF, "LOW", "Low resolution"),
+    opcode(Op::HighResolution, 0x00FF, 0xFFFF, "HIGH", "High resolution"),
+    opcode(Op::Jump, 0x1000, 0xF000, "JP nnn", "Jump"),
+    opcode(Op::Call, 0x2000, 0xF000, "CALL nnn", "Call"),
+    opcode(Op::SkipEqualByte, 0x3000, 0xF000, "SE Vx, nn", "Skip if Equals Byte"),
+    opcode(Op::SkipNotEqualByte, 0x4000, 0xF000, "SNE Vx, nn", "Skip if not equals"),
+    opcode(Op::SkipEqual, 0x5000, 0xF00F, "SE Vx, Vy", "Skip if Equals"),
+    opcode(Op::SaveRange, 0x5002, 0xF00F, "SAVE Vx - Vy", "Save register range"),
+    opcode(Op::LoadRange, 0x5003, 0xF00F, "LOAD Vx - Vy", "Load register range"),
+    opcode(Op::LoadByte, 0x6000, 0xF000, "LD Vx, nn", "Load byte"),
+    opcode(Op::AddByte, 0x7000, 0xF000, "ADD Vx, nn", "Add byte"),
+    opcode(Op::Move, 0x8000, 0xF00F, "LD Vx, Vy", "Move"),
+    opcode(Op::Or, 0x8001, 0xF00F, "OR Vx, Vy", "Or"),
+    opcode(Op::And, 0x8002, 0xF00F, "AND Vx, Vy", "And"),
+    opcode(Op::Xor, 0x8003, 0xF00F, "XOR Vx, Vy", "Xor"),
+    opcode(Op::Add, 0x8004, 0xF00F, "ADD Vx, Vy", "Add"),
+    opcode(Op::Sub, 0x8005, 0xF00F, "SUB Vx, Vy", "Sub"),
+    opcode(Op::ShiftRight, 0x8006, 0xF00F, "SHR Vx, Vy", "Shift Right"),
+    opcode(Op::ReverseSub, 0x8007, 0xF00F, "SUBN Vx, Vy", "Reverse sub"),
+    opcode(Op::ShiftLeft, 0x800E, 0xF00F, "SHL Vx, Vy", "Shift left"),
+    opcode(Op::SkipNotEqual, 0x9000, 0xF00F, "SNE Vx, Vy", "Skip if not equal"),
+    opcode(Op::LoadI, 0xA000, 0xF000, "LD I, nnn", "Load i"),
+    opcode(Op::JumpOffset, 0xB000, 0xF000, "JP V0, nnn", "Jump Plus Zero"),
+    opcode(Op::Random, 0xC000, 0xF000, "RND Vx, nn", "Random"),
+    opcode(Op::Draw, 0xD000, 0xF000, "DRW Vx, Vy, n", "Draw"),
+    opcode(Op::SkipPressed, 0xE09E, 0xF0FF, "SKP Vx", "Skip if pressed"),
+    opcode(Op::SkipNotPressed, 0xE0A1, 0xF0FF, "SKNP Vx", "Skip if not pressed"),
+    opcode(Op::LoadLongI, 0xF000, 0xFFFF, "LD I, long nnnn", "Load long i"),
+    opcode(Op::SelectPlanes, 0xF001, 0xF0FF, "PLANE x", "Select planes"),
+    opcode(Op::LoadAudio, 0xF002, 0xFFFF, "AUDIO", "Load audio pattern"),
+    opcode(Op::LoadDelay, 0xF007, 0xF0FF, "LD Vx, DT", "Load Delay Timer"),
+    opcode(Op::WaitKey, 0xF00A, 0xF0FF, "LD Vx, K", "Wait for key press"),
+    opcode(Op::SetDelay, 0xF015, 0xF0FF, "LD DT, Vx", "Set delay timer"),
+    opcode(Op::SetSound, 0xF018, 0xF0FF, "LD ST, Vx", "Set sound timer"),
+    opcode(Op::AddI, 0xF01E, 0xF0FF, "ADD I, Vx", "Add to i"),
+    opcode(Op::LoadFont, 0xF029, 0xF0FF, "LD F, Vx", "Load Sprite"),
+    opcode(Op::LoadBigFont, 0xF030, 0xF0FF, "LD HF, Vx", "Load Big Sprite"),
+    opcode(Op::SetPitch, 0xF03A, 0xF0FF, "PITCH Vx", "Set pitch"),
+    opcode(Op::Bcd, 0xF033, 0xF0FF, "LD B, Vx", "BCD Representation"),
+    opcode(Op::Store, 0xF055, 0xF0FF, "LD [I], Vx", "Store register"),
+    opcode(Op::Load, 0xF065, 0xF0FF, "LD Vx, [I]", "Load Register"),
+    opcode(Op::StoreFlags, 0xF075, 0xF0FF, "LD R, Vx", "Store RPL flags"),
+    opcode(Op::LoadFlags, 0xF085, 0xF0FF, "LD Vx, R", "Load RPL flags"),
+];
+
+pub fn decode(opcode: u16) -> Option<&'static Opcode> {
+    return OPCODES.iter().find(|entry| opcode & entry.mask == entry.pattern);
+}
+
+/// Formats an instruction in the table's syntax. `long` is the word that
+/// follows `F000`, and `label` replaces the address operand when given.
+pub fn format(entry: &Opcode, opcode: u16, long: u16, label: Option<&str>) -> String {
+    let address = |text: String| -> String {
+        return match label {
+            Some(label) => label.to_string(),
+            None => text,
+        };
+    };
+
+    let operands: Vec<String> = entry
+        .syntax
+        .split(' ')
+        .map(|token| {
+            let (field, separator) = match token.strip_suffix(',') {
+                Some(field) => (field, ","),
+                None => (token, ""),
+            };
+
+            let value = match field {
+                "Vx" => format!("V{:X}", opcode >> 8 & 0xF),
+                "Vy" => format!("V{:X}", opcode >> 4 & 0xF),
+                "nnnn" => address(format!("0x{:04X}", long)),
+                "nnn" => address(format!("0x{:03X}", opcode & 0xFFF)),
+                "nn" => format!("0x{:02X}", opcode & 0xFF),
+                "n" => format!("{}", opcode & 0xF),
+                "x" => format!("{}", opcode >> 8 & 0xF),
+                literal => literal.to_string(),
+            };
+
+            return value + separator;
+        })
+        .collect();
+
+    return operands.join(" ");
+}
@@ -0,0 +1,412 @@
+use rust_chip::capture::{DEFAULT_SCALE, MAX_SCALE};
+use rust_chip::cpu::{PROGRAM_START, STACK_DEPTH};
+use rust_chip::debugger;
+use rust_chip::filter::Mode;
+use rust_chip::rewind::DEFAULT_BUDGET_MB;
+use std::path::PathBuf;
+
+const USAGE: &str = "Usage: rust-chip [OPTIONS] <ROM>
+       rust-chip disasm [-o <FILE>] <ROM>
+       rust-chip assemble [-o <FILE>] <SOURCE>
+       rust-chip headless [OPTIONS] <ROM>
+
+The ROM may be gzipped or inside a zip archive, and is read from stdin when
+given as '-'.
+
+Options:
+    -s, --scale <N>      Window scale factor (default: 12)
+    -t, --terminal       Draw in the terminal and read keys from it instead of opening a window
+        --braille        Draw with braille dots, 2x4 pixels per character, in the terminal
+    -f, --fullscreen     Start in fullscreen (F11 toggles it)
+        --integer-scale  Only scale the screen by whole numbers
+        --theme <NAME>   Colors: classic, phosphor, amber, lcd or contrast (default: classic)
+        --palette <LIST> Comma separated #RRGGBB colors replacing color0 onward
+        --filter <NAME>  Anti-flicker filter: off, or, blend[:N] or decay (default: off, F9 cycles)
+    -i, --ipf <N>        Instructions executed per 60 Hz frame (default: 10)
+        --hz <N>         Instructions executed per second, instead of --ipf
+    -q, --quirks <NAME>  Quirk profile: vip, chip48, schip, xochip or modern (default: modern)
+        --stack <N>      Nested calls allowed before a stack overflow (default: 16)
+    -a, --address <ADDR> Load address of the ROM, 0x600 for ETI-660 programs (default: 0x200)
+    -c, --config <FILE>  Global config file (default: ~/.config/rust-chip/config.ini)
+    -p, --paused         Start with the emulator paused (P toggles pause)
+    -d, --debug          Read debugger commands from stdin (type 'help')
+    -b, --break <ADDR>   Break when PC reaches ADDR, may be repeated
+    -l, --state <FILE>   Start from a save state written by Shift+F1 to F4
+    -r, --rewind <MB>    Memory kept for rewinding with Backspace (default: 16, 0 disables)
+        --seed <N>       Seed for the CXNN random numbers (default: random)
+        --record <FILE>  Record the keypad of every frame into a movie
+        --play <FILE>    Replay a movie recorded with --record
+    -m, --mute           Do not open an audio device
+    -w, --wav <FILE>     Record the beeper into a WAV file instead of playing it
+        --png <FILE>     Save the screen as a PNG when quitting (F12 saves one next to the ROM)
+        --capture <FILE> Record every frame as a GIF, or raw RGB for other extensions (F10 toggles)
+        --zoom <N>       Image pixels per CHIP-8 pixel in screenshots and captures (default: 4, at most 511)
+        --capture-audio  Also record the beeper of captures into a WAV file next to them
+    -h, --help           Print this message";
+
+const DISASM_USAGE: &str = "Usage: rust-chip disasm [OPTIONS] <ROM>
+
+Prints the ROM as annotated assembly.
+
+Options:
+    -a, --address <ADDR> Load address of the ROM, 0x600 for ETI-660 programs (default: 0x200)
+    -o, --output <FILE>  Write the listing to FILE instead of stdout
+    -h, --help           Print this message";
+
+const ASSEMBLE_USAGE: &str = "Usage: rust-chip assemble [OPTIONS] <SOURCE>
+
+Assembles a source file into a ROM.
+
+Options:
+    -a, --address <ADDR> Load address of the ROM, 0x600 for ETI-660 programs (default: 0x200)
+    -o, --output <FILE>  Where to write the ROM (default: SOURCE with a .ch8 extension)
+    -h, --help           Print this message";
+
+const HEADLESS_USAGE: &str = "Usage: rust-chip headless [OPTIONS] <ROM>
+
+Runs a ROM without a window until it jumps to itself, exits or runs out of
+frames, then saves or checks the screen.
+
+Options:
+    -f, --frames <N>     Frames to run at most (default: 600)
+    -i, --ipf <N>        Instructions executed per 60 Hz frame (default: 30)
+    -q, --quirks <NAME>  Quirk profile: vip, chip48, schip, xochip or modern (default: modern)
+    -a, --address <ADDR> Load address of the ROM, 0x600 for ETI-660 programs (default: 0x200)
+        --pbm <FILE>     Save the screen as a plain PBM image
+        --png <FILE>     Save the screen as a PNG image
+    -s, --scale <N>      Image pixels per CHIP-8 pixel in the PNG image (default: 1)
+        --theme <NAME>   Colors: classic, phosphor, amber, lcd or contrast (default: classic)
+        --palette <LIST> Comma separated #RRGGBB colors replacing color0 onward
+        --hash           Print a hash of the screen
+        --golden <FILE>  Fail unless the screen matches this PBM image
+    -h, --help           Print this message";
+
+// The window settings are unused by builds without SDL.
+#[cfg_attr(not(feature = "sdl"), allow(dead_code))]
+pub struct Options {
+    pub rom: PathBuf,
+    pub scale: isize,
+    pub terminal: bool,
+    pub braille: bool,
+    pub fullscreen: bool,
+    pub integer_scale: bool,
+    pub theme: Option<String>,
+    pub palette: Option<String>,
+    pub filter: Mode,
+    pub speed: u8,
+    pub hz: Option<u32>,
+    pub quirks: Option<String>,
+    pub stack: usize,
+    pub address: u16,
+    pub config: Option<PathBuf>,
+    pub paused: bool,
+    pub debug: bool,
+    pub breakpoints: Vec<u16>,
+    pub state: Option<PathBuf>,
+    pub rewind: usize,
+    pub seed: Option<u64>,
+    pub record: Option<PathBuf>,
+    pub play: Option<PathBuf>,
+    pub mute: bool,
+    pub wav: Option<PathBuf>,
+    pub screenshot: Option<PathBuf>,
+    pub capture: Option<PathBuf>,
+    pub capture_scale: usize,
+    pub capture_audio: bool,
+}
+
+impl Options {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<Options, String> {
+        let mut rom: Option<PathBuf> = None;
+        let mut scale = 12;
+        // Builds without SDL can only draw in the terminal.
+        let mut terminal = !cfg!(feature = "sdl");
+        let mut braille = false;
+        let mut fullscreen = false;
+        let mut integer_scale = false;
+        let mut theme: Option<String> = None;
+        let mut palette: Option<String> = None;
+        let mut filter = Mode::Off;
+        let mut speed = 10;
+        let mut hz: Option<u32> = None;
+        let mut quirks: Option<String> = None;
+        let mut stack = STACK_DEPTH;
+        let mut address = PROGRAM_START as u16;
+        let mut config: Option<PathBuf> = None;
+        let mut paused = false;
+        let mut debug = false;
+        let mut breakpoints = Vec::new();
+        let mut state: Option<PathBuf> = None;
+        let mut rewind = DEFAULT_BUDGET_MB;
+        let mut seed: Option<u64> = None;
+        let mut record: Option<PathBuf> = None;
+        let mut play: Option<PathBuf> = None;
+        let mut mute = false;
+        let mut wav: Option<PathBuf> = None;
+        let mut screenshot: Option<PathBuf> = None;
+        let mut capture: Option<PathBuf> = None;
+        let mut capture_scale = DEFAULT_SCALE;
+        let mut capture_audio = false;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Err(USAGE.to_string()),
+                "-s" | "--scale" => scale = parse_number(&arg, args.next())?,
+                "-t" | "--terminal" => terminal = true,
+                "--braille" => braille = true,
+                "-f" | "--fullscreen" => fullscreen = true,
+                "--integer-scale" => integer_scale = true,
+                "--theme" => theme = Some(required_value(&arg, args.next())?),
+                "--palette" => palette = Some(required_value(&arg, args.next())?),
+                "--filter" => filter = Mode::from_name(&required_value(&arg, args.next())?)?,
+                "-i" | "--ipf" => {
+                    speed = parse_number(&arg, args.next())?;
+                    hz = None;
+                }
+                "--hz" => hz = Some(parse_number(&arg, args.next())?),
+                "-q" | "--quirks" => quirks = Some(required_value(&arg, args.next())?),
+                "--stack" => stack = parse_number(&arg, args.next())?,
+                "-a" | "--address" => address = parse_address(&arg, args.next())?,
+                "-c" | "--config" => config = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                "-p" | "--paused" => paused = true,
+                "-d" | "--debug" => debug = true,
+                "-b" | "--break" => {
+                    let address = required_value(&arg, args.next())?;
+                    breakpoints.push(debugger::parse_number(&address)?);
+                }
+                "-l" | "--state" => state = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                "-r" | "--rewind" => rewind = parse_number(&arg, args.next())?,
+                "--seed" => seed = Some(parse_number(&arg, args.next())?),
+                "--record" => record = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                "--play" => play = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                "-m" | "--mute" => mute = true,
+                "-w" | "--wav" => wav = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                "--png" => screenshot = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                "--capture" => capture = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                "--zoom" => capture_scale = parse_number(&arg, args.next())?,
+                "--capture-audio" => capture_audio = true,
+                flag if flag.starts_with('-') && flag != "-" => {
+                    return Err(format!("Unknown option '{}'\n\n{}", flag, USAGE));
+                }
+                path => {
+                    if rom.is_some() {
+                        return Err(format!("Unexpected argument '{}'\n\n{}", path, USAGE));
+                    }
+                    rom = Some(PathBuf::from(path));
+                }
+            }
+        }
+
+        let rom = rom.ok_or(format!("No ROM file given\n\n{}", USAGE))?;
+
+        if scale <= 0 || capture_scale == 0 {
+            return Err(String::from("Scale must be greater than zero"));
+        }
+
+        if capture_scale > MAX_SCALE {
+            return Err(format!("Zoom must be at most {}", MAX_SCALE));
+        }
+
+        if speed == 0 || hz == Some(0) {
+            return Err(String::from("Instructions per frame must be greater than zero"));
+        }
+
+        if stack == 0 || stack > u8::MAX as usize {
+            return Err(String::from("Stack depth must be between 1 and 255"));
+        }
+
+        if debug && rom.as_os_str() == "-" {
+            return Err(String::from("The debugger reads its commands from stdin, so the ROM cannot come from there"));
+        }
+
+        if terminal && rom.as_os_str() == "-" {
+            return Err(String::from("The terminal frontend reads keys from stdin, so the ROM cannot come from there"));
+        }
+
+        if terminal && debug {
+            return Err(String::from("The terminal frontend reads keys from stdin, which the debugger needs for its commands"));
+        }
+
+        if (record.is_some() || play.is_some()) && state.is_some() {
+            return Err(String::from("Movies start from power-on and cannot be combined with --state"));
+        }
+
+        return Ok(Options {
+            rom,
+            scale,
+            terminal,
+            braille,
+            fullscreen,
+            integer_scale,
+            theme,
+            palette,
+            filter,
+            speed,
+            hz,
+            quirks,
+            stack,
+            address,
+            config,
+            paused,
+            debug,
+            breakpoints,
+            state,
+            rewind,
+            seed,
+            record,
+            play,
+            mute,
+            wav,
+            screenshot,
+            capture,
+            capture_scale,
+            capture_audio,
+        });
+    }
+}
+
+fn required_value(flag: &str, value: Option<String>) -> Result<String, String> {
+    return value.ok_or(format!("Option '{}' requires a value", flag));
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let value = required_value(flag, value)?;
+
+    return value
+        .parse()
+        .map_err(|_| format!("Invalid value '{}' for option '{}'", value, flag));
+}
+
+fn parse_address(flag: &str, value: Option<String>) -> Result<u16, String> {
+    let value = required_value(flag, value)?;
+
+    return debugger::parse_number(&value).map_err(|_| format!("Invalid address '{}' for option '{}'", value, flag));
+}
+
+/// Arguments of the `headless` subcommand.
+pub struct HeadlessOptions {
+    pub rom: PathBuf,
+    pub frames: u32,
+    pub speed: u8,
+    pub quirks: Option<String>,
+    pub address: u16,
+    pub pbm: Option<PathBuf>,
+    pub png: Option<PathBuf>,
+    pub theme: Option<String>,
+    pub palette: Option<String>,
+    pub scale: usize,
+    pub hash: bool,
+    pub golden: Option<PathBuf>,
+}
+
+impl HeadlessOptions {
+    pub fn parse<I: Iterator<Item = String>>(mut args: I) -> Result<HeadlessOptions, String> {
+        let mut rom: Option<PathBuf> = None;
+        let mut frames = 600;
+        let mut speed = 30;
+        let mut quirks: Option<String> = None;
+        let mut address = PROGRAM_START as u16;
+        let mut pbm: Option<PathBuf> = None;
+        let mut png: Option<PathBuf> = None;
+        let mut theme: Option<String> = None;
+        let mut palette: Option<String> = None;
+        let mut scale = 1;
+        let mut hash = false;
+        let mut golden: Option<PathBuf> = None;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Err(HEADLESS_USAGE.to_string()),
+                "-f" | "--frames" => frames = parse_number(&arg, args.next())?,
+                "-i" | "--ipf" => speed = parse_number(&arg, args.next())?,
+                "-q" | "--quirks" => quirks = Some(required_value(&arg, args.next())?),
+                "-a" | "--address" => address = parse_address(&arg, args.next())?,
+                "--pbm" => pbm = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                "--png" => png = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                "--theme" => theme = Some(required_value(&arg, args.next())?),
+                "--palette" => palette = Some(required_value(&arg, args.next())?),
+                "-s" | "--scale" => scale = parse_number(&arg, args.next())?,
+                "--hash" => hash = true,
+                "--golden" => golden = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                flag if flag.starts_with('-') && flag != "-" => {
+                    return Err(format!("Unknown option '{}'\n\n{}", flag, HEADLESS_USAGE));
+                }
+                path => {
+                    if rom.is_some() {
+                        return Err(format!("Unexpected argument '{}'\n\n{}", path, HEADLESS_USAGE));
+                    }
+                    rom = Some(PathBuf::from(path));
+                }
+            }
+        }
+
+        let rom = rom.ok_or(format!("No ROM file given\n\n{}", HEADLESS_USAGE))?;
+
+        if speed == 0 {
+            return Err(String::from("Instructions per frame must be greater than zero"));
+        }
+
+        if scale == 0 {
+            return Err(String::from("Scale must be greater than zero"));
+        }
+
+        return Ok(HeadlessOptions {
+            rom,
+            frames,
+            speed,
+            quirks,
+            address,
+            pbm,
+            png,
+            theme,
+            palette,
+            scale,
+            hash,
+            golden,
+        });
+    }
+}
+
+/// Arguments of the subcommands that turn one file into another.
+pub struct ToolOptions {
+    pub input: PathBuf,
+    pub output: Option<PathBuf>,
+    pub address: u16,
+}
+
+impl ToolOptions {
+    pub fn parse_disasm<I: Iterator<Item = String>>(args: I) -> Result<ToolOptions, String> {
+        return ToolOptions::parse(DISASM_USAGE, args);
+    }
+
+    pub fn parse_assemble<I: Iterator<Item = String>>(args: I) -> Result<ToolOptions, String> {
+        return ToolOptions::parse(ASSEMBLE_USAGE, args);
+    }
+
+    fn parse<I: Iterator<Item = String>>(usage: &str, mut args: I) -> Result<ToolOptions, String> {
+        let mut input: Option<PathBuf> = None;
+        let mut output: Option<PathBuf> = None;
+        let mut address = PROGRAM_START as u16;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-h" | "--help" => return Err(usage.to_string()),
+                "-o" | "--output" => output = Some(PathBuf::from(required_value(&arg, args.next())?)),
+                "-a" | "--address" => address = parse_address(&arg, args.next())?,
+                flag if flag.starts_with('-') && flag != "-" => {
+                    return Err(format!("Unknown option '{}'\n\n{}", flag, usage));
+                }
+                path => {
+                    if input.is_some() {
+                        return Err(format!("Unexpected argument '{}'\n\n{}", path, usage));
+                    }
+                    input = Some(PathBuf::from(path));
+                }
+            }
+        }
+
+        let input = input.ok_or(format!("No input file given\n\n{}", usage))?;
+
+        return Ok(ToolOptions { input, output, address });
+    }
+}
@@ -0,0 +1,126 @@
+use crate::config::Config;
+use crate::framebuffer::PLANES;
+
+pub const COLORS: usize = 1 << PLANES;
+
+/// Names accepted by `Palette::from_name`.
+pub const THEMES: [&str; 5] = ["classic", "phosphor", "amber", "lcd", "contrast"];
+
+/// Maps framebuffer values to 0xRRGGBB colors. Index 0 is the background,
+/// 1 is a lit pixel and, in XO-CHIP mode, the other indexes are the
+/// combinations of bitplanes.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Palette {
+    pub colors: Vec<u32>,
+}
+
+impl Palette {
+    pub fn new() -> Palette {
+        return Palette {
+            colors: vec![
+                0x000000, 0xFFFFFF, 0xAAAAAA, 0x555555, 0xFF0000, 0x00FF00, 0x0000FF, 0xFFFF00,
+                0x880000, 0x008800, 0x000088, 0x888800, 0xFF00FF, 0x00FFFF, 0x880088, 0x008888,
+            ],
+        };
+    }
+
+    /// A named theme. Besides `classic` they set the background, both planes
+    /// and their overlap, and fade from the background to the lit color for
+    /// the combinations of four planes.
+    pub fn from_name(name: &str) -> Result<Palette, String> {
+        let colors = match name.to_lowercase().as_str() {
+            "classic" => return Ok(Palette::new()),
+            "phosphor" | "green" => [0x001200, 0x33FF66, 0x1A8033, 0xAAFFBB],
+            "amber" => [0x140A00, 0xFFB000, 0x805800, 0xFFDD88],
+            "lcd" => [0x9BBC0F, 0x0F380F, 0x306230, 0x5B7A1E],
+            "contrast" | "high-contrast" => [0x000000, 0xFFFFFF, 0xFFFF00, 0x00FFFF],
+            _ => {
+                return Err(format!(
+                    "Unknown theme '{}', expected one of: {}",
+                    name,
+                    THEMES.join(", ")
+                ))
+            }
+        };
+
+        let mut palette = Palette::new();
+        palette.colors[..4].copy_from_slice(&colors);
+        for index in 4..COLORS {
+            palette.colors[index] = mix(colors[0], colors[1], index as u32, COLORS as u32 - 1);
+        }
+
+        return Ok(palette);
+    }
+
+    /// Reads the `[palette]` section: `theme` picks a theme and `color0` to
+    /// `color15` override its colors as `#RRGGBB`.
+    pub fn from_config(config: &Config) -> Result<Palette, String> {
+        let mut palette = match config.get("palette", "theme") {
+            Some(name) => Palette::from_name(name)?,
+            None => Palette::new(),
+        };
+
+        for (key, value) in config.section("palette") {
+            if key == "theme" {
+                continue;
+            }
+            let index = key
+                .strip_prefix("color")
+                .and_then(|index| index.parse::<usize>().ok())
+                .filter(|&index| index < COLORS)
+                .ok_or(format!("Unknown palette entry '{}', expected color0 to color15", key))?;
+
+            palette.colors[index] = parse_color(value)?;
+        }
+
+        return Ok(palette);
+    }
+
+    pub fn color(&self, index: u32) -> u32 {
+        return self.colors[index as usize % COLORS];
+    }
+
+    /// Replaces the colors from `color0` on by a comma separated list, e.g.
+    /// `#000000,#FFFFFF` for a 2-color or up to 16 colors for XO-CHIP.
+    pub fn override_colors(&mut self, list: &str) -> Result<(), String> {
+        let colors: Vec<&str> = list.split(',').map(|color| color.trim()).collect();
+        if colors.len() > COLORS {
+            return Err(format!("A palette has at most {} colors, got {}", COLORS, colors.len()));
+        }
+
+        for (index, color) in colors.iter().enumerate() {
+            self.colors[index] = parse_color(color)?;
+        }
+
+        return Ok(());
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Palette {
+        return Palette::new();
+    }
+}
+
+pub fn parse_color(value: &str) -> Result<u32, String> {
+    let hex = value.trim_start_matches('#');
+
+    if hex.len() != 6 {
+        return Err(format!("Invalid color '{}', expected #RRGGBB", value));
+    }
+
+    return u32::from_str_radix(hex, 16).map_err(|_| format!("Invalid color '{}', expected #RRGGBB", value));
+}
+
+/// Blends `from` into `to` by `weight / total`, per channel.
+fn mix(from: u32, to: u32, weight: u32, total: u32) -> u32 {
+    let mut color = 0;
+
+    for shift in [16, 8, 0].iter() {
+        let a = (from >> shift) & 0xFF;
+        let b = (to >> shift) & 0xFF;
+        color |= ((a * (total - weight) + b * weight) / total) << shift;
+    }
+
+    return color;
+}
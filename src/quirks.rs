@@ -0,0 +1,136 @@
+use crate::config::{parse_bool, Config};
+
+pub const PROFILES: [&str; 5] = ["vip", "chip48", "schip", "xochip", "modern"];
+
+/// Behaviours that differ between CHIP-8 interpreters and that ROMs rely on.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Quirks {
+    /// `8XY6`/`8XYE` shift `Vy` into `Vx` instead of shifting `Vx` in place.
+    pub shift_uses_vy: bool,
+    /// `FX55`/`FX65` leave `I` pointing past the last register stored/loaded.
+    pub load_store_increments_i: bool,
+    /// `BNNN` behaves as `BXNN`, jumping to `XNN + Vx` instead of `NNN + V0`.
+    pub jump_uses_vx: bool,
+    /// `8XY1`/`8XY2`/`8XY3` reset `VF` to zero.
+    pub vf_reset: bool,
+    /// Sprites are clipped at the screen edges instead of wrapping around.
+    pub clip_sprites: bool,
+    /// `DXYN` waits for the next 60 Hz frame, allowing one draw per frame.
+    pub display_wait: bool,
+    /// XO-CHIP extensions: 64 KiB of memory, bitplanes and audio patterns.
+    pub xo_chip: bool,
+    /// `FX0A` completes when the key is released again instead of as soon as
+    /// it is pressed.
+    pub key_wait_release: bool,
+}
+
+impl Quirks {
+    /// The original COSMAC VIP interpreter.
+    pub fn cosmac_vip() -> Quirks {
+        return Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            vf_reset: true,
+            clip_sprites: true,
+            display_wait: true,
+            xo_chip: false,
+            key_wait_release: true,
+        };
+    }
+
+    /// CHIP-48 on the HP-48 calculators.
+    pub fn chip48() -> Quirks {
+        return Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: true,
+            vf_reset: false,
+            clip_sprites: true,
+            display_wait: false,
+            xo_chip: false,
+            key_wait_release: true,
+        };
+    }
+
+    /// SUPER-CHIP 1.1.
+    pub fn schip() -> Quirks {
+        return Quirks::chip48();
+    }
+
+    /// XO-CHIP as implemented by Octo.
+    pub fn xo_chip() -> Quirks {
+        return Quirks {
+            shift_uses_vy: true,
+            load_store_increments_i: true,
+            jump_uses_vx: false,
+            vf_reset: false,
+            clip_sprites: false,
+            display_wait: false,
+            xo_chip: true,
+            key_wait_release: true,
+        };
+    }
+
+    /// What most present-day interpreters and this one historically do.
+    pub fn modern() -> Quirks {
+        return Quirks {
+            shift_uses_vy: false,
+            load_store_increments_i: false,
+            jump_uses_vx: false,
+            vf_reset: false,
+            clip_sprites: false,
+            display_wait: false,
+            xo_chip: false,
+            key_wait_release: false,
+        };
+    }
+
+    pub fn from_name(name: &str) -> Result<Quirks, String> {
+        match name.to_lowercase().as_str() {
+            "vip" | "cosmac" => Ok(Quirks::cosmac_vip()),
+            "chip48" => Ok(Quirks::chip48()),
+            "schip" => Ok(Quirks::schip()),
+            "xochip" => Ok(Quirks::xo_chip()),
+            "modern" => Ok(Quirks::modern()),
+            _ => Err(format!(
+                "Unknown quirk profile '{}', expected one of: {}",
+                name,
+                PROFILES.join(", ")
+            )),
+        }
+    }
+
+    /// Builds the quirks from the `[quirks]` section: `profile` picks a
+    /// preset and any individual flag overrides it.
+    pub fn from_config(config: &Config) -> Result<Quirks, String> {
+        let mut quirks = match config.get("quirks", "profile") {
+            Some(name) => Quirks::from_name(name)?,
+            None => Quirks::default(),
+        };
+
+        for (key, value) in config.section("quirks") {
+            let flag = match key {
+                "profile" => continue,
+                "shift_uses_vy" => &mut quirks.shift_uses_vy,
+                "load_store_increments_i" => &mut quirks.load_store_increments_i,
+                "jump_uses_vx" => &mut quirks.jump_uses_vx,
+                "vf_reset" => &mut quirks.vf_reset,
+                "clip_sprites" => &mut quirks.clip_sprites,
+                "display_wait" => &mut quirks.display_wait,
+                "xo_chip" => &mut quirks.xo_chip,
+                "key_wait_release" => &mut quirks.key_wait_release,
+                _ => return Err(format!("Unknown quirk '{}'", key)),
+            };
+            *flag = parse_bool(key, value)?;
+        }
+
+        return Ok(quirks);
+    }
+}
+
+impl Default for Quirks {
+    fn default() -> Quirks {
+        return Quirks::modern();
+    }
+}
@@ -1,114 +1,150 @@
-use sdl2::{EventPump, Sdl};
-use sdl2::pixels::Color;
+use crate::filter::{Filter, Mode};
+use crate::framebuffer::{Framebuffer, COLUMNS, ROWS};
+use crate::frontend::Display;
+use crate::palette::Palette;
+use sdl2::pixels::{Color, PixelFormatEnum};
 use sdl2::rect::Rect;
+use sdl2::render::{Canvas, Texture, TextureCreator};
+use sdl2::video::{FullscreenType, Window, WindowContext};
+use sdl2::Sdl;
 use std::convert::TryInto;
-use crate::keyboard::Keyboard;
-
-const ROWS: isize = 32;
-const COLUMNS: isize = 64; 
 
 pub struct Renderer {
-    pub keyboard: Keyboard,
-    scale: isize,
-    display: Vec<u32>,
-    pub sdl_context: Sdl,
-    pub event_pump: EventPump,
-    canvas: sdl2::render::Canvas<sdl2::video::Window>,
+    palette: Palette,
+    canvas: Canvas<Window>,
+    creator: TextureCreator<WindowContext>,
+    /// Holds one texel per CHIP-8 pixel and is streamed to once per frame,
+    /// then scaled up by the GPU. Recreated when the resolution changes.
+    /// With `unsafe_textures` it has to be destroyed while the canvas is
+    /// still open, so it is only `None` while being dropped.
+    texture: Option<Texture>,
+    /// Produces the RGB pixels uploaded to the texture.
+    filter: Filter,
+    width: isize,
+    integer_scale: bool,
 }
 
 impl Renderer {
-
-    pub fn new(scale: isize, keyboard: Keyboard) -> Result<Renderer, String> {
-        let display: Vec<u32> = vec![0; (ROWS*COLUMNS) as usize];
-
-        let sdl_context = sdl2::init()?;
+    /// Opens a resizable window of 64x32 pixels times `scale`. The screen is
+    /// letterboxed to keep its 2:1 aspect ratio, and with `integer_scale`
+    /// only scaled by whole numbers so every pixel has the same size.
+    pub fn new(
+        sdl_context: &Sdl,
+        scale: isize,
+        palette: Palette,
+        filter: Mode,
+        fullscreen: bool,
+        integer_scale: bool,
+    ) -> Result<Renderer, String> {
         let video_subsystem = sdl_context.video()?;
 
-        let window = video_subsystem
-            .window("Rust Chip", (COLUMNS * scale).try_into().unwrap(), ((ROWS * scale)).try_into().unwrap())
-            .position_centered()
-            .opengl()
-            .build()
-            .map_err(|e| e.to_string())?;
-    
+        let mut window = video_subsystem.window("Rust Chip", (COLUMNS * scale).try_into().unwrap(), (ROWS * scale).try_into().unwrap());
+        window.position_centered().resizable();
+        if fullscreen {
+            window.fullscreen_desktop();
+        }
+        let window = window.build().map_err(|e| e.to_string())?;
+
         let canvas = window.into_canvas().build().map_err(|e| e.to_string())?;
 
-        let mut event_pump = sdl_context.event_pump().unwrap();
-        event_pump.disable_event(sdl2::event::EventType::MouseMotion);
-        event_pump.disable_event(sdl2::event::EventType::MouseButtonUp);
-        event_pump.disable_event(sdl2::event::EventType::MouseButtonDown);
-        event_pump.disable_event(sdl2::event::EventType::MouseWheel);
+        let creator = canvas.texture_creator();
+        let texture = creator
+            .create_texture_streaming(PixelFormatEnum::RGB24, COLUMNS as u32, ROWS as u32)
+            .map_err(|e| e.to_string())?;
 
         return Ok(Renderer {
-            keyboard,
-            event_pump,
-            scale,
-            display,
-            sdl_context,
+            palette,
             canvas,
+            creator,
+            texture: Some(texture),
+            filter: Filter::new(filter),
+            width: COLUMNS,
+            integer_scale,
         });
     }
 
-    pub fn render(&mut self) -> u8 {
+    /// The largest 2:1 area centered in the window.
+    fn screen_rect(&self) -> Result<Rect, String> {
+        let (width, height) = self.canvas.output_size()?;
+        let mut scale = f64::min(width as f64 / COLUMNS as f64, height as f64 / ROWS as f64);
+        if self.integer_scale && scale >= 1.0 {
+            scale = scale.floor();
+        }
 
-        self.canvas.set_draw_color(Color::BLACK);
-        self.canvas.clear();
-        self.canvas.present();
+        let screen_width = (COLUMNS as f64 * scale) as u32;
+        let screen_height = (ROWS as f64 * scale) as u32;
 
-        for i in 0..ROWS*COLUMNS {
-            // Grabs the x position of the pixel based off of `i`
-            let x = (i % COLUMNS) * self.scale;
-    
-            // Grabs the y position of the pixel based off of `i`
-            let y = (((i / COLUMNS) * self.scale) as f32).floor() as isize;
-    
-            self.canvas.set_draw_color(Color::WHITE);
-            // If the value at this.display[i] == 1, then draw a pixel.
-
-            let t = i as usize;
-            if self.display[t] > 0 {    
-                // Place a pixel at position (x, y) with a width and height of scale
-                self.canvas.fill_rect(Rect::new(x.try_into().unwrap(), y.try_into().unwrap(), self.scale as u32, self.scale as u32)).unwrap();
-            }
+        return Ok(Rect::new(
+            ((width - screen_width) / 2) as i32,
+            ((height - screen_height) / 2) as i32,
+            screen_width.max(1),
+            screen_height.max(1),
+        ));
+    }
+}
 
-            /*self.canvas.fill_rect(Rect::new(0, 0, self.scale as u32, self.scale as u32));
-            self.canvas.fill_rect(Rect::new(0, 1, self.scale as u32, self.scale as u32));
-            self.canvas.fill_rect(Rect::new(0, 2, self.scale as u32, self.scale as u32));
-            self.canvas.fill_rect(Rect::new(0, 3, self.scale as u32, self.scale as u32));
-            self.canvas.fill_rect(Rect::new(0, 4, self.scale as u32, self.scale as u32));
-            self.canvas.fill_rect(Rect::new(0, 5, self.scale as u32, self.scale as u32));*/
+impl Display for Renderer {
+    fn render(&mut self, framebuffer: &Framebuffer) -> Result<(), String> {
+        if framebuffer.width != self.width {
+            let texture = self
+                .creator
+                .create_texture_streaming(PixelFormatEnum::RGB24, framebuffer.width as u32, framebuffer.height as u32)
+                .map_err(|e| e.to_string())?;
+            if let Some(old) = self.texture.replace(texture) {
+                // The canvas it was created from is still open.
+                unsafe { old.destroy() };
+            }
+            self.width = framebuffer.width;
         }
+
+        // Any lit plane draws the pixel in the palette color of its planes.
+        let pixels = self.filter.apply(framebuffer, &self.palette);
+        self.texture
+            .as_mut()
+            .unwrap()
+            .update(None, &pixels, (framebuffer.width * 3) as usize)
+            .map_err(|e| e.to_string())?;
+
+        // The letterbox bars take the background color.
+        let screen = self.screen_rect()?;
+        self.canvas.set_draw_color(rgb(self.palette.color(0)));
+        self.canvas.clear();
+        self.canvas.copy(self.texture.as_ref().unwrap(), None, screen)?;
         self.canvas.present();
 
-        return 0;
+        return Ok(());
     }
 
-    pub fn set_pixel(&mut self, mut x: isize, mut y: isize) -> bool {
-        if x > COLUMNS {
-            x -= COLUMNS;
-        } else if x < 0 {
-            x += COLUMNS;
-        }
-        
-        if y > ROWS {
-            y -= ROWS;
-        } else if y < 0 {
-            y += ROWS;
-        }
-        
-        let x_pos = x as usize;
-        let y_pos = (y * COLUMNS) as usize;
-        let pixel_loc: usize = x_pos + y_pos;
+    fn toggle_fullscreen(&mut self) -> Result<(), String> {
+        let window = self.canvas.window_mut();
+        let fullscreen = match window.fullscreen_state() {
+            FullscreenType::Off => FullscreenType::Desktop,
+            _ => FullscreenType::Off,
+        };
 
-        self.display[pixel_loc] ^= 1;
+        return window.set_fullscreen(fullscreen);
+    }
 
-        return self.display[pixel_loc] > 0;
+    fn cycle_filter(&mut self) -> Option<String> {
+        self.filter.set_mode(self.filter.mode.next());
+
+        return Some(self.filter.mode.name());
+    }
+
+    fn is_animating(&self) -> bool {
+        return self.filter.is_animating();
     }
+}
 
-    pub fn clear(&mut self) {
-        self.display.clear();
-        for _n in 0..ROWS*COLUMNS {
-            self.display.push(0);
+impl Drop for Renderer {
+    fn drop(&mut self) {
+        if let Some(texture) = self.texture.take() {
+            // Fields are dropped after this, so the canvas is still open.
+            unsafe { texture.destroy() };
         }
     }
 }
+
+fn rgb(color: u32) -> Color {
+    return Color::RGB((color >> 16) as u8, (color >> 8) as u8, color as u8);
+}
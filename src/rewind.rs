@@ -0,0 +1,142 @@
+use std::collections::VecDeque;
+
+/// Default memory kept for rewinding, in MiB.
+pub const DEFAULT_BUDGET_MB: usize = 16;
+
+/// The difference between two consecutive snapshots.
+struct Entry {
+    /// Turns the newer snapshot back into the older one, see `diff`.
+    delta: Vec<u8>,
+    /// Instructions executed between the two snapshots.
+    instructions: u32,
+}
+
+/// A ring buffer of save states for stepping backward in time.
+///
+/// Only the newest snapshot is kept whole. Older ones are stored as deltas
+/// that each undo one step, which are small because a frame rarely touches
+/// more than a few bytes, and the oldest are dropped once `budget` bytes
+/// are in use.
+pub struct Rewind {
+    pub budget: usize,
+    entries: VecDeque<Entry>,
+    current: Vec<u8>,
+    used: usize,
+}
+
+impl Rewind {
+    pub fn new(budget: usize) -> Rewind {
+        return Rewind {
+            budget,
+            entries: VecDeque::new(),
+            current: Vec::new(),
+            used: 0,
+        };
+    }
+
+    /// Adds `state` as the newest snapshot, `instructions` after the previous one.
+    pub fn record(&mut self, state: Vec<u8>, instructions: u32) {
+        if self.budget == 0 {
+            return;
+        }
+
+        if !self.current.is_empty() {
+            let delta = diff(&state, &self.current);
+            self.used += delta.len();
+            self.entries.push_back(Entry { delta, instructions });
+        }
+        self.used = self.used + state.len() - self.current.len();
+        self.current = state;
+
+        while self.used > self.budget {
+            match self.entries.pop_front() {
+                Some(entry) => self.used -= entry.delta.len(),
+                None => break,
+            }
+        }
+    }
+
+    /// Drops the newest snapshot and returns the one before it, together with
+    /// the number of instructions that separated them.
+    pub fn back(&mut self) -> Option<(Vec<u8>, u32)> {
+        let entry = self.entries.pop_back()?;
+        let older = patch(&self.current, &entry.delta);
+
+        self.used = self.used + older.len() - self.current.len() - entry.delta.len();
+        self.current = older.clone();
+
+        return Some((older, entry.instructions));
+    }
+
+    /// How many steps back are available.
+    pub fn len(&self) -> usize {
+        return self.entries.len();
+    }
+
+    pub fn is_empty(&self) -> bool {
+        return self.entries.is_empty();
+    }
+
+    /// Bytes used by the snapshot and the deltas.
+    pub fn memory_used(&self) -> usize {
+        return self.used;
+    }
+}
+
+/// Encodes how to turn `from` into `to`: the length of `to`, followed by the
+/// XOR of both as alternating runs of unchanged and changed bytes, each run
+/// prefixed with its length.
+fn diff(from: &[u8], to: &[u8]) -> Vec<u8> {
+    let length = from.len().max(to.len());
+    let xor = |index: usize| from.get(index).unwrap_or(&0) ^ to.get(index).unwrap_or(&0);
+    let mut out = Vec::new();
+    out.extend_from_slice(&(to.len() as u32).to_le_bytes());
+
+    let mut index = 0;
+    while index < length {
+        let start = index;
+        while index < length && index - start < u16::MAX as usize && xor(index) == 0 {
+            index += 1;
+        }
+        out.extend_from_slice(&((index - start) as u16).to_le_bytes());
+
+        let start = index;
+        while index < length && index - start < u16::MAX as usize && xor(index) != 0 {
+            index += 1;
+        }
+        out.extend_from_slice(&((index - start) as u16).to_le_bytes());
+        out.extend((start..index).map(xor));
+    }
+
+    return out;
+}
+
+fn patch(from: &[u8], delta: &[u8]) -> Vec<u8> {
+    let length = u32::from_le_bytes([delta[0], delta[1], delta[2], delta[3]]) as usize;
+    let mut out = from.to_vec();
+    out.resize(from.len().max(length), 0);
+
+    let mut position = 4;
+    let mut index = 0;
+
+    while position < delta.len() {
+        index += read_run(delta, &mut position);
+        let changed = read_run(delta, &mut position);
+        for byte in delta[position..position + changed].iter() {
+            out[index] ^= byte;
+            index += 1;
+        }
+        position += changed;
+    }
+
+    out.truncate(length);
+
+    return out;
+}
+
+fn read_run(delta: &[u8], position: &mut usize) -> usize {
+    let run = u16::from_le_bytes([delta[*position], delta[*position + 1]]) as usize;
+    *position += 2;
+
+    return run;
+}
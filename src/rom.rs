@@ -0,0 +1,117 @@
+use crate::error::Chip8Error;
+use flate2::read::{DeflateDecoder, GzDecoder};
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+/// Where the ETI-660 loads its programs, instead of the usual 0x200.
+pub const ETI_660_START: u16 = 0x600;
+
+/// File name extensions of CHIP-8 programs, looked for inside zip archives.
+pub const EXTENSIONS: [&str; 4] = ["ch8", "c8", "sc8", "xo8"];
+
+/// Reads a ROM from `path`, or from stdin when the path is `-`. Gzip files
+/// and zip archives holding a single program are unpacked.
+pub fn read(path: &Path) -> Result<Vec<u8>, Chip8Error> {
+    let unreadable = |reason: String| Chip8Error::RomUnreadable {
+        path: path.to_path_buf(),
+        reason,
+    };
+
+    let data = if path == Path::new("-") {
+        let mut data = Vec::new();
+        io::stdin().read_to_end(&mut data).map_err(|e| unreadable(e.to_string()))?;
+        data
+    } else {
+        fs::read(path).map_err(|e| unreadable(e.to_string()))?
+    };
+
+    return unpack(data).map_err(unreadable);
+}
+
+/// Returns the program inside a gzip file or zip archive, or `data` itself
+/// when it is neither.
+pub fn unpack(data: Vec<u8>) -> Result<Vec<u8>, String> {
+    if data.starts_with(&[0x1F, 0x8B]) {
+        let mut rom = Vec::new();
+        GzDecoder::new(&data[..])
+            .read_to_end(&mut rom)
+            .map_err(|e| format!("invalid gzip file: {}", e))?;
+
+        return Ok(rom);
+    }
+    if data.starts_with(b"PK\x03\x04") {
+        return unzip(&data);
+    }
+
+    return Ok(data);
+}
+
+/// Extracts the only program of a zip archive, going through the central
+/// directory since local headers may leave the sizes out.
+fn unzip(data: &[u8]) -> Result<Vec<u8>, String> {
+    let invalid = || String::from("invalid zip archive");
+    let u16_at = |at: usize| {
+        data.get(at..at + 2)
+            .map(|b| u16::from_le_bytes([b[0], b[1]]) as usize)
+            .ok_or_else(invalid)
+    };
+    let u32_at = |at: usize| {
+        data.get(at..at + 4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+            .ok_or_else(invalid)
+    };
+
+    // The end of central directory record is the last thing in the file,
+    // followed only by a comment of up to 64 KiB.
+    let end = (0..data.len().saturating_sub(21))
+        .rev()
+        .take(0x10000 + 22)
+        .find(|&at| data[at..].starts_with(b"PK\x05\x06"))
+        .ok_or_else(invalid)?;
+    let count = u16_at(end + 10)?;
+    let mut entry = u32_at(end + 16)?;
+
+    let mut programs = Vec::new();
+    for _ in 0..count {
+        if u32_at(entry)? != 0x0201_4B50 {
+            return Err(invalid());
+        }
+        let name_length = u16_at(entry + 28)?;
+        let name = data.get(entry + 46..entry + 46 + name_length).ok_or_else(invalid)?;
+        let name = String::from_utf8_lossy(name).to_string();
+        let is_program = Path::new(&name)
+            .extension()
+            .map(|extension| EXTENSIONS.iter().any(|known| extension.eq_ignore_ascii_case(known)))
+            .unwrap_or(false);
+
+        if is_program {
+            programs.push((name, u16_at(entry + 10)?, u32_at(entry + 20)?, u32_at(entry + 42)?));
+        }
+        entry += 46 + name_length + u16_at(entry + 30)? + u16_at(entry + 32)?;
+    }
+
+    let (name, method, size, header) = match programs.len() {
+        1 => programs.remove(0),
+        0 => return Err(String::from("the zip archive holds no .ch8 file")),
+        _ => {
+            let names: Vec<String> = programs.into_iter().map(|program| program.0).collect();
+            return Err(format!("the zip archive holds several programs: {}", names.join(", ")));
+        }
+    };
+
+    let start = header + 30 + u16_at(header + 26)? + u16_at(header + 28)?;
+    let compressed = data.get(start..start + size).ok_or_else(invalid)?;
+
+    return match method {
+        0 => Ok(compressed.to_vec()),
+        8 => {
+            let mut rom = Vec::new();
+            DeflateDecoder::new(compressed)
+                .read_to_end(&mut rom)
+                .map_err(|e| format!("could not unpack '{}': {}", name, e))?;
+            Ok(rom)
+        }
+        _ => Err(format!("'{}' uses an unsupported zip compression method {}", name, method)),
+    };
+}
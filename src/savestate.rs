@@ -0,0 +1,242 @@
+use crate::cpu::{Cpu, KeyWait, MEMORY_SIZE, RPL_FLAGS, XO_CHIP_MEMORY_SIZE};
+use crate::framebuffer::{COLUMNS, HIRES_COLUMNS, HIRES_ROWS, ROWS};
+use crate::quirks::Quirks;
+use crate::rng::Rng;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// The first bytes of every save state file.
+const MAGIC: &[u8; 8] = b"CHIP8SAV";
+
+/// Bumped whenever the layout changes. Files of any other version are
+/// rejected instead of being misread.
+pub const VERSION: u16 = 3;
+
+/// FNV-1a, which unlike `DefaultHasher` stays the same across Rust releases
+/// and so can be stored in files.
+pub fn rom_hash(rom: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+
+    for byte in rom.iter() {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x0100_0000_01b3);
+    }
+
+    return hash;
+}
+
+/// Where quick-save slot `slot` of a ROM lives, e.g. `roms/BRIX.state1`.
+pub fn slot_path(rom: &Path, slot: u8) -> PathBuf {
+    return rom.with_extension(format!("state{}", slot));
+}
+
+/// Serialises everything a running ROM can observe. Host settings such as
+/// the speed or the pause state are not part of it.
+pub fn save(cpu: &Cpu) -> Vec<u8> {
+    let mut out = Vec::new();
+
+    out.extend_from_slice(MAGIC);
+    out.extend_from_slice(&VERSION.to_le_bytes());
+    out.extend_from_slice(&cpu.rom_hash.to_le_bytes());
+    out.push(quirk_flags(&cpu.quirks));
+
+    out.extend_from_slice(&(cpu.memory.len() as u32).to_le_bytes());
+    out.extend_from_slice(&cpu.memory);
+    out.extend_from_slice(&cpu.v);
+    out.extend_from_slice(&cpu.i.to_le_bytes());
+    out.extend_from_slice(&cpu.pc.to_le_bytes());
+    out.push(cpu.sp as u8);
+    for address in cpu.call_stack().iter() {
+        out.extend_from_slice(&address.to_le_bytes());
+    }
+    out.push(cpu.delay_timer);
+    out.push(cpu.sound_timer);
+    out.extend_from_slice(&cpu.rpl);
+    out.extend_from_slice(&cpu.audio_pattern);
+    out.push(cpu.pitch);
+    out.push(cpu.vblank as u8);
+    out.push(cpu.exited as u8);
+    out.extend_from_slice(&cpu.rng.state.to_le_bytes());
+    let (wait, keys) = match cpu.key_wait {
+        KeyWait::Idle => (0, 0),
+        KeyWait::Waiting { held } => (1, held),
+        KeyWait::Pressed(key) => (2, key as u16),
+    };
+    out.push(wait);
+    out.extend_from_slice(&keys.to_le_bytes());
+
+    let framebuffer = &cpu.framebuffer;
+    out.push(framebuffer.hires as u8);
+    out.push(framebuffer.planes as u8);
+    out.extend(framebuffer.pixels.iter().map(|pixel| *pixel as u8));
+
+    return out;
+}
+
+/// Restores a state written by `save`. The CPU is left untouched when the
+/// state is invalid or was saved while running a different ROM.
+pub fn load(cpu: &mut Cpu, data: &[u8]) -> Result<(), String> {
+    let mut reader = Reader { data, position: 0 };
+
+    if reader.bytes(MAGIC.len())? != MAGIC {
+        return Err(String::from("Not a save state file"));
+    }
+    let version = reader.u16()?;
+    if version != VERSION {
+        return Err(format!("Unsupported save state version {}, expected {}", version, VERSION));
+    }
+    let hash = reader.u64()?;
+    if hash != cpu.rom_hash {
+        return Err(String::from("The save state belongs to a different ROM"));
+    }
+
+    let quirks = quirks_from_flags(reader.u8()?);
+    let memory_size = reader.u32()? as usize;
+    let memory = reader.bytes(memory_size)?.to_vec();
+    let v = reader.bytes(16)?.to_vec();
+    let i = reader.u16()?;
+    let pc = reader.u16()?;
+    let depth = reader.u8()? as usize;
+    if depth > cpu.stack.len() {
+        return Err(format!("Save state has a stack of {} entries", depth));
+    }
+    let mut stack = Vec::new();
+    for _ in 0..depth {
+        stack.push(reader.u16()?);
+    }
+    let delay_timer = reader.u8()?;
+    let sound_timer = reader.u8()?;
+    let rpl = reader.bytes(RPL_FLAGS)?.to_vec();
+    let audio_pattern = reader.bytes(16)?.to_vec();
+    let pitch = reader.u8()?;
+    let vblank = reader.u8()? != 0;
+    let exited = reader.u8()? != 0;
+    let rng = reader.u64()?;
+    let key_wait = match (reader.u8()?, reader.u16()?) {
+        (0, _) => KeyWait::Idle,
+        (1, held) => KeyWait::Waiting { held },
+        (2, key) if key < 16 => KeyWait::Pressed(key as u8),
+        (wait, keys) => return Err(format!("Save state has an invalid key wait {} {:04X}", wait, keys)),
+    };
+    let hires = reader.u8()? != 0;
+    let planes = reader.u8()? as u32;
+
+    let expected = if quirks.xo_chip { XO_CHIP_MEMORY_SIZE } else { MEMORY_SIZE };
+    if memory.len() != expected {
+        return Err(format!("Save state has {} bytes of memory, expected {}", memory.len(), expected));
+    }
+    let (width, height) = if hires { (HIRES_COLUMNS, HIRES_ROWS) } else { (COLUMNS, ROWS) };
+    let pixels = reader.bytes((width * height) as usize)?;
+    if reader.position != data.len() {
+        return Err(String::from("Save state has trailing data"));
+    }
+
+    cpu.quirks = quirks;
+    cpu.framebuffer.set_hires(hires);
+    cpu.framebuffer.pixels = pixels.iter().map(|pixel| *pixel as u32).collect();
+    cpu.framebuffer.planes = planes;
+    cpu.framebuffer.dirty = true;
+    cpu.memory = memory;
+    cpu.v = v;
+    cpu.i = i;
+    cpu.pc = pc;
+    cpu.stack[..depth].copy_from_slice(&stack);
+    cpu.sp = depth;
+    cpu.delay_timer = delay_timer;
+    cpu.sound_timer = sound_timer;
+    cpu.rpl = rpl;
+    cpu.audio_pattern = audio_pattern;
+    cpu.pitch = pitch;
+    cpu.vblank = vblank;
+    cpu.exited = exited;
+    cpu.rng = Rng::new(rng);
+    cpu.key_wait = key_wait;
+
+    return Ok(());
+}
+
+pub fn save_file(cpu: &Cpu, path: &Path) -> Result<(), String> {
+    return fs::write(path, save(cpu)).map_err(|e| format!("Could not write '{}': {}", path.display(), e));
+}
+
+pub fn load_file(cpu: &mut Cpu, path: &Path) -> Result<(), String> {
+    let data = fs::read(path).map_err(|e| format!("Could not read '{}': {}", path.display(), e))?;
+
+    return load(cpu, &data).map_err(|e| format!("{}: {}", path.display(), e));
+}
+
+pub(crate) fn quirk_flags(quirks: &Quirks) -> u8 {
+    let flags = [
+        quirks.shift_uses_vy,
+        quirks.load_store_increments_i,
+        quirks.jump_uses_vx,
+        quirks.vf_reset,
+        quirks.clip_sprites,
+        quirks.display_wait,
+        quirks.xo_chip,
+        quirks.key_wait_release,
+    ];
+
+    return flags
+        .iter()
+        .enumerate()
+        .fold(0, |bits, (index, flag)| bits | (*flag as u8) << index);
+}
+
+pub(crate) fn quirks_from_flags(bits: u8) -> Quirks {
+    let flag = |index: u8| bits & (1 << index) != 0;
+
+    return Quirks {
+        shift_uses_vy: flag(0),
+        load_store_increments_i: flag(1),
+        jump_uses_vx: flag(2),
+        vf_reset: flag(3),
+        clip_sprites: flag(4),
+        display_wait: flag(5),
+        xo_chip: flag(6),
+        key_wait_release: flag(7),
+    };
+}
+
+pub(crate) struct Reader<'a> {
+    pub data: &'a [u8],
+    pub position: usize,
+}
+
+impl<'a> Reader<'a> {
+    pub fn bytes(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.position + count;
+        if end > self.data.len() {
+            return Err(String::from("File is truncated"));
+        }
+
+        let bytes = &self.data[self.position..end];
+        self.position = end;
+
+        return Ok(bytes);
+    }
+
+    pub fn u8(&mut self) -> Result<u8, String> {
+        return Ok(self.bytes(1)?[0]);
+    }
+
+    pub fn u16(&mut self) -> Result<u16, String> {
+        let bytes = self.bytes(2)?;
+
+        return Ok(u16::from_le_bytes([bytes[0], bytes[1]]));
+    }
+
+    pub fn u32(&mut self) -> Result<u32, String> {
+        let mut bytes = [0; 4];
+        bytes.copy_from_slice(self.bytes(4)?);
+
+        return Ok(u32::from_le_bytes(bytes));
+    }
+
+    pub fn u64(&mut self) -> Result<u64, String> {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(self.bytes(8)?);
+
+        return Ok(u64::from_le_bytes(bytes));
+    }
+}
@@ -0,0 +1,90 @@
+use std::time::{Duration, Instant};
+
+/// The delay and sound timers always count down at this rate.
+pub const TIMER_HZ: u32 = 60;
+
+/// When the host falls further behind than this many timer ticks, the
+/// missing time is dropped instead of emulated, so a stalled window does not
+/// make the game fast-forward afterwards.
+pub const MAX_CATCH_UP_TICKS: u32 = 5;
+
+pub fn tick_period() -> Duration {
+    return Duration::from_nanos(1_000_000_000 / TIMER_HZ as u64);
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ClockRate {
+    /// A fixed number of instructions between two timer ticks.
+    PerFrame(u32),
+    /// Instructions per second, spread as evenly as possible across ticks.
+    Hertz(u32),
+}
+
+/// Decides, from a monotonic clock, how many 60 Hz timer ticks are due and
+/// how many instructions to execute in each of them.
+pub struct Scheduler {
+    pub rate: ClockRate,
+    pub skipped_ticks: u64,
+    ticks: u64,
+    next_tick: Instant,
+}
+
+impl Scheduler {
+    pub fn new(rate: ClockRate) -> Scheduler {
+        return Scheduler {
+            rate,
+            skipped_ticks: 0,
+            ticks: 0,
+            next_tick: Instant::now(),
+        };
+    }
+
+    /// Restarts the clock so that the first tick is due at `now`.
+    pub fn start(&mut self, now: Instant) {
+        self.next_tick = now;
+    }
+
+    /// Returns how many ticks are due at `now` and schedules the next one.
+    pub fn due_ticks(&mut self, now: Instant) -> u32 {
+        if now < self.next_tick {
+            return 0;
+        }
+
+        let period = tick_period();
+        let behind = now - self.next_tick;
+        let due = 1 + (behind.as_nanos() / period.as_nanos()) as u32;
+
+        if due > MAX_CATCH_UP_TICKS {
+            self.skipped_ticks += (due - MAX_CATCH_UP_TICKS) as u64;
+            self.next_tick = now + period;
+
+            return MAX_CATCH_UP_TICKS;
+        }
+
+        self.next_tick += period * due;
+
+        return due;
+    }
+
+    /// Number of instructions to run before the next timer tick.
+    pub fn instructions_for_tick(&mut self) -> u32 {
+        match self.rate {
+            ClockRate::PerFrame(count) => count,
+            ClockRate::Hertz(hz) => {
+                let before = self.ticks * hz as u64 / TIMER_HZ as u64;
+                self.ticks += 1;
+                let after = self.ticks * hz as u64 / TIMER_HZ as u64;
+
+                (after - before) as u32
+            }
+        }
+    }
+
+    pub fn time_until_next_tick(&self, now: Instant) -> Duration {
+        if now >= self.next_tick {
+            return Duration::from_secs(0);
+        }
+
+        return self.next_tick - now;
+    }
+}
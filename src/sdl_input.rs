@@ -0,0 +1,243 @@
+use crate::config::Config;
+use crate::frontend::{Command, Input};
+use crate::keyboard::{HostKeys, Keyboard, Source};
+use crate::keymap::{Action, Keymap};
+use sdl2::controller::{Button, GameController};
+use sdl2::event::{Event, EventType, WindowEvent};
+use sdl2::keyboard::{Keycode, Mod};
+use sdl2::{EventPump, GameControllerSubsystem, Sdl};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+pub struct SdlInput {
+    event_pump: EventPump,
+    pub keymap: Keymap,
+    /// Game controller support, when SDL could start it.
+    controllers: Option<GameControllerSubsystem>,
+    /// Connected controllers by SDL instance id, with their buttons.
+    gamepads: HashMap<u32, (GameController, Keymap)>,
+    /// The keypad keys held on the keyboard and on each controller.
+    host_keys: HostKeys,
+    /// The global and per-ROM settings, for the profiles of controllers
+    /// plugged in later.
+    config: Config,
+    /// Where the remap screen saves the new keypad bindings.
+    config_path: Option<PathBuf>,
+    /// The keypad key the remap screen asks for, and the bindings to go back
+    /// to when it is cancelled.
+    remapping: Option<(u8, Keymap)>,
+}
+
+impl SdlInput {
+    pub fn new(sdl_context: &Sdl, config: &Config, config_path: Option<PathBuf>) -> Result<SdlInput, String> {
+        let keymap = Keymap::from_config(config)?;
+        if let Some(name) = keymap.key_names().into_iter().find(|name| Keycode::from_name(name).is_none()) {
+            return Err(format!("Unknown key name '{}' in the key bindings", name));
+        }
+        SdlInput::check_buttons(&Keymap::gamepad_from_config(config, "")?)?;
+
+        // Already connected controllers are announced as added devices by
+        // the first poll, like the ones plugged in later.
+        let controllers = match sdl_context.game_controller() {
+            Ok(controllers) => Some(controllers),
+            Err(e) => {
+                eprintln!("No game controller support, using the keyboard only: {}", e);
+                None
+            }
+        };
+
+        let mut event_pump = sdl_context.event_pump()?;
+        event_pump.disable_event(EventType::MouseMotion);
+        event_pump.disable_event(EventType::MouseButtonUp);
+        event_pump.disable_event(EventType::MouseButtonDown);
+        event_pump.disable_event(EventType::MouseWheel);
+
+        return Ok(SdlInput {
+            event_pump,
+            keymap,
+            controllers,
+            gamepads: HashMap::new(),
+            host_keys: HostKeys::new(),
+            config: config.clone(),
+            config_path,
+            remapping: None,
+        });
+    }
+
+    fn check_buttons(keymap: &Keymap) -> Result<(), String> {
+        return match keymap.key_names().into_iter().find(|name| Button::from_string(name).is_none()) {
+            Some(name) => Err(format!("Unknown game controller button '{}'", name)),
+            None => Ok(()),
+        };
+    }
+
+    fn add_gamepad(&mut self, joystick_index: u32) {
+        let controller = match self.controllers.as_ref().map(|controllers| controllers.open(joystick_index)) {
+            Some(Ok(controller)) => controller,
+            Some(Err(e)) => return eprintln!("Could not open game controller {}: {}", joystick_index, e),
+            None => return,
+        };
+
+        let name = controller.name();
+        let buttons = match Keymap::gamepad_from_config(&self.config, &name) {
+            Ok(buttons) => SdlInput::check_buttons(&buttons).map(|_| buttons),
+            Err(message) => Err(message),
+        };
+        let buttons = buttons.unwrap_or_else(|message| {
+            eprintln!("{}, using the default buttons for '{}'", message, name);
+            Keymap::gamepad()
+        });
+
+        println!("Connected game controller '{}'", name);
+        self.gamepads.insert(controller.instance_id(), (controller, buttons));
+    }
+
+    /// Carries out the action of a key or button of `source` going down or,
+    /// when `pressed` is false, up again.
+    fn trigger(
+        &mut self,
+        source: Source,
+        action: Action,
+        pressed: bool,
+        repeat: bool,
+        keyboard: &mut Keyboard,
+        commands: &mut Vec<Command>,
+    ) {
+        if let Action::Key(key) = action {
+            self.host_keys.set(source, key, pressed);
+            keyboard.set(self.host_keys.held());
+            return;
+        }
+
+        if !pressed {
+            if action == Action::Rewind {
+                commands.push(Command::Rewind(false));
+            }
+            return;
+        }
+
+        match action {
+            Action::Key(_) => {}
+            Action::StepInstruction => commands.push(Command::StepInstruction),
+            Action::StepFrame => commands.push(Command::StepFrame),
+            _ if repeat => {}
+            Action::Quit => commands.push(Command::Quit),
+            Action::Pause => commands.push(Command::TogglePause),
+            Action::Rewind => commands.push(Command::Rewind(true)),
+            Action::Save(slot) => commands.push(Command::SaveState(slot)),
+            Action::Load(slot) => commands.push(Command::LoadState(slot)),
+            Action::Fullscreen => commands.push(Command::ToggleFullscreen),
+            Action::Filter => commands.push(Command::CycleFilter),
+            Action::Screenshot => commands.push(Command::Screenshot),
+            Action::Capture => commands.push(Command::ToggleCapture),
+            Action::Remap => {
+                println!("Press the keys for 0 to F in turn, or Escape to cancel");
+                self.remapping = Some((0, self.keymap.clone()));
+                commands.push(Command::Remap(Some(0)));
+            }
+        }
+    }
+
+    /// Binds `keycode` to the keypad key being asked for and moves on to the
+    /// next one. Escape cancels.
+    fn remap(&mut self, keycode: Keycode, commands: &mut Vec<Command>) {
+        let (key, previous) = match self.remapping.take() {
+            Some(remapping) => remapping,
+            None => return,
+        };
+
+        if keycode == Keycode::Escape {
+            self.keymap = previous;
+            println!("Key remapping cancelled");
+            commands.push(Command::Remap(None));
+            return;
+        }
+
+        self.keymap.bind(Action::Key(key), &[&keycode.name()]);
+        if key < 0xF {
+            self.remapping = Some((key + 1, previous));
+            commands.push(Command::Remap(Some(key + 1)));
+            return;
+        }
+
+        commands.push(Command::Remap(None));
+        if let Some(path) = &self.config_path {
+            match Config::write_section(path, "keys", &self.keymap.keypad_entries()) {
+                Ok(()) => println!("Saved key bindings to {}", path.display()),
+                Err(message) => eprintln!("{}", message),
+            }
+        }
+    }
+}
+
+impl Input for SdlInput {
+    fn poll(&mut self, keyboard: &mut Keyboard) -> Vec<Command> {
+        let mut commands = Vec::new();
+        let events: Vec<Event> = self.event_pump.poll_iter().collect();
+
+        for event in events {
+            match event {
+                Event::Quit { .. } => {
+                    commands.push(Command::Quit);
+                }
+                Event::Window {
+                    win_event: WindowEvent::Exposed | WindowEvent::SizeChanged(..),
+                    ..
+                } => {
+                    commands.push(Command::Redraw);
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    repeat: false,
+                    ..
+                } if self.remapping.is_some() => {
+                    self.remap(keycode, &mut commands);
+                }
+                Event::KeyDown {
+                    keycode: Some(keycode),
+                    keymod,
+                    repeat,
+                    ..
+                } => {
+                    let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+
+                    if let Some(action) = self.keymap.action(&keycode.name(), shift) {
+                        self.trigger(Source::Keyboard, action, true, repeat, keyboard, &mut commands);
+                    }
+                }
+                Event::KeyUp {
+                    keycode: Some(keycode),
+                    keymod,
+                    ..
+                } => {
+                    let shift = keymod.intersects(Mod::LSHIFTMOD | Mod::RSHIFTMOD);
+
+                    if let Some(action) = self.keymap.action(&keycode.name(), shift) {
+                        self.trigger(Source::Keyboard, action, false, false, keyboard, &mut commands);
+                    }
+                }
+                Event::ControllerDeviceAdded { which, .. } => self.add_gamepad(which),
+                Event::ControllerDeviceRemoved { which, .. } => {
+                    if let Some((controller, _)) = self.gamepads.remove(&which) {
+                        println!("Disconnected game controller '{}'", controller.name());
+                    }
+                    self.host_keys.remove_gamepad(which);
+                    keyboard.set(self.host_keys.held());
+                }
+                Event::ControllerButtonDown { which, button, .. } | Event::ControllerButtonUp { which, button, .. }
+                    if self.remapping.is_none() =>
+                {
+                    let pressed = matches!(event, Event::ControllerButtonDown { .. });
+                    let action = self.gamepads.get(&which).and_then(|(_, buttons)| buttons.action(&button.string(), false));
+
+                    if let Some(action) = action {
+                        self.trigger(Source::Gamepad(which), action, pressed, false, keyboard, &mut commands);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        return commands;
+    }
+}
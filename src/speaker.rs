@@ -0,0 +1,195 @@
+use crate::frontend::Audio;
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+pub const SAMPLE_RATE: u32 = 44100;
+pub const TONE_FREQUENCY: f32 = 440.0;
+pub const VOLUME: f32 = 0.25;
+
+/// How many samples one 60 Hz timer tick lasts.
+pub const SAMPLES_PER_TICK: usize = (SAMPLE_RATE / 60) as usize;
+
+/// Bits in an XO-CHIP audio pattern.
+const PATTERN_BITS: f32 = 128.0;
+
+/// Square-wave oscillator that is silent unless `beeping` is set. Once an
+/// XO-CHIP pattern is set it plays that pattern's bits instead.
+pub struct SquareWave {
+    pub beeping: bool,
+    sample_rate: u32,
+    phase: f32,
+    phase_inc: f32,
+    pattern: Option<[u8; 16]>,
+    pattern_phase: f32,
+    pattern_inc: f32,
+}
+
+impl SquareWave {
+    pub fn new(frequency: f32, sample_rate: u32) -> SquareWave {
+        return SquareWave {
+            beeping: false,
+            sample_rate,
+            phase: 0.0,
+            phase_inc: frequency / sample_rate as f32,
+            pattern: None,
+            pattern_phase: 0.0,
+            pattern_inc: 0.0,
+        };
+    }
+
+    /// Plays `pattern` at 4000 * 2^((pitch - 64) / 48) bits per second.
+    pub fn set_pattern(&mut self, pattern: &[u8], pitch: u8) {
+        let mut bits = [0; 16];
+        bits.copy_from_slice(&pattern[..16]);
+
+        let rate = 4000.0 * 2f32.powf((pitch as f32 - 64.0) / 48.0);
+        self.pattern = Some(bits);
+        self.pattern_inc = rate / self.sample_rate as f32;
+    }
+
+    pub fn next_sample(&mut self) -> f32 {
+        if !self.beeping {
+            return 0.0;
+        }
+
+        if let Some(pattern) = &self.pattern {
+            let bit = self.pattern_phase as usize;
+            let set = pattern[bit / 8] & (0x80 >> (bit % 8)) != 0;
+            self.pattern_phase = (self.pattern_phase + self.pattern_inc) % PATTERN_BITS;
+
+            return if set { VOLUME } else { -VOLUME };
+        }
+
+        let sample = if self.phase < 0.5 { VOLUME } else { -VOLUME };
+        self.phase = (self.phase + self.phase_inc) % 1.0;
+
+        return sample;
+    }
+}
+
+/// Plays the beeper through the default SDL audio device.
+#[cfg(feature = "sdl")]
+pub struct Speaker {
+    device: sdl2::audio::AudioDevice<SquareWave>,
+}
+
+#[cfg(feature = "sdl")]
+impl sdl2::audio::AudioCallback for SquareWave {
+    type Channel = f32;
+
+    fn callback(&mut self, out: &mut [f32]) {
+        for sample in out.iter_mut() {
+            *sample = self.next_sample();
+        }
+    }
+}
+
+#[cfg(feature = "sdl")]
+impl Speaker {
+    pub fn new(sdl_context: &sdl2::Sdl) -> Result<Speaker, String> {
+        let audio_subsystem = sdl_context.audio()?;
+
+        let desired_spec = sdl2::audio::AudioSpecDesired {
+            freq: Some(SAMPLE_RATE as i32),
+            channels: Some(1),
+            samples: None,
+        };
+
+        let device = audio_subsystem.open_playback(None, &desired_spec, |spec| {
+            SquareWave::new(TONE_FREQUENCY, spec.freq as u32)
+        })?;
+        device.resume();
+
+        return Ok(Speaker { device });
+    }
+}
+
+#[cfg(feature = "sdl")]
+impl Audio for Speaker {
+    fn set_beeping(&mut self, beeping: bool) {
+        self.device.lock().beeping = beeping;
+    }
+
+    fn set_pattern(&mut self, pattern: &[u8], pitch: u8) {
+        self.device.lock().set_pattern(pattern, pitch);
+    }
+}
+
+/// Records the beeper into a 16-bit mono WAV file instead of playing it.
+/// Every `set_beeping` call appends one 60 Hz tick worth of samples, so the
+/// capture stays in step with the emulated timers rather than the host clock.
+pub struct WavSpeaker {
+    writer: BufWriter<File>,
+    wave: SquareWave,
+    samples: u32,
+}
+
+impl WavSpeaker {
+    pub fn new(path: &Path) -> Result<WavSpeaker, String> {
+        let file = File::create(path)
+            .map_err(|e| format!("Could not create WAV file '{}': {}", path.display(), e))?;
+
+        let mut speaker = WavSpeaker {
+            writer: BufWriter::new(file),
+            wave: SquareWave::new(TONE_FREQUENCY, SAMPLE_RATE),
+            samples: 0,
+        };
+        // The sizes are patched in once the capture is finished.
+        speaker.write_header().map_err(|e| e.to_string())?;
+
+        return Ok(speaker);
+    }
+
+    fn write_header(&mut self) -> std::io::Result<()> {
+        let data_size = self.samples * 2;
+
+        self.writer.write_all(b"RIFF")?;
+        self.writer.write_all(&(36 + data_size).to_le_bytes())?;
+        self.writer.write_all(b"WAVEfmt ")?;
+        self.writer.write_all(&16u32.to_le_bytes())?;
+        self.writer.write_all(&1u16.to_le_bytes())?; // PCM
+        self.writer.write_all(&1u16.to_le_bytes())?; // mono
+        self.writer.write_all(&SAMPLE_RATE.to_le_bytes())?;
+        self.writer.write_all(&(SAMPLE_RATE * 2).to_le_bytes())?;
+        self.writer.write_all(&2u16.to_le_bytes())?; // block align
+        self.writer.write_all(&16u16.to_le_bytes())?; // bits per sample
+        self.writer.write_all(b"data")?;
+        self.writer.write_all(&data_size.to_le_bytes())?;
+
+        return Ok(());
+    }
+
+    fn finish(&mut self) -> std::io::Result<()> {
+        self.writer.seek(SeekFrom::Start(0))?;
+        self.write_header()?;
+        self.writer.flush()?;
+
+        return Ok(());
+    }
+}
+
+impl Audio for WavSpeaker {
+    fn set_beeping(&mut self, beeping: bool) {
+        self.wave.beeping = beeping;
+
+        for _ in 0..SAMPLES_PER_TICK {
+            let sample = (self.wave.next_sample() * i16::MAX as f32) as i16;
+            if self.writer.write_all(&sample.to_le_bytes()).is_ok() {
+                self.samples += 1;
+            }
+        }
+    }
+
+    fn set_pattern(&mut self, pattern: &[u8], pitch: u8) {
+        self.wave.set_pattern(pattern, pitch);
+    }
+}
+
+impl Drop for WavSpeaker {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            eprintln!("Could not finish WAV capture: {}", e);
+        }
+    }
+}
@@ -0,0 +1,425 @@
+use crate::config::Config;
+use crate::filter::{Filter, Mode};
+use crate::framebuffer::Framebuffer;
+use crate::frontend::{Audio, Command, Display, Input};
+use crate::keyboard::Keyboard;
+use crate::keymap::{Action, Keymap};
+use crate::palette::Palette;
+use std::collections::HashMap;
+use std::io::{self, Read, Write};
+use std::process::{Command as Process, Stdio};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// How long a key counts as held after being pressed. Terminals only report
+/// presses, so a key is released once this passes without the terminal
+/// repeating it; it has to outlast the delay before the key repeat starts,
+/// which defaults to 500 to 660 ms on common desktops.
+const FIRST_HOLD: Duration = Duration::from_millis(700);
+
+/// How long a key counts as held after each repeat.
+const REPEAT_HOLD: Duration = Duration::from_millis(100);
+
+/// Draws the screen with Unicode half blocks, two pixels per character, or
+/// braille patterns, eight pixels per character, in 24-bit color.
+pub struct TerminalDisplay {
+    palette: Palette,
+    filter: Filter,
+    braille: bool,
+    /// The lines drawn last, so only the ones that changed are sent again.
+    lines: Vec<String>,
+}
+
+impl TerminalDisplay {
+    pub fn new(palette: Palette, filter: Mode, braille: bool) -> TerminalDisplay {
+        // Clear the screen and hide the cursor.
+        print!("\x1b[2J\x1b[?25l");
+
+        return TerminalDisplay {
+            palette,
+            filter: Filter::new(filter),
+            braille,
+            lines: Vec::new(),
+        };
+    }
+}
+
+impl Display for TerminalDisplay {
+    fn render(&mut self, framebuffer: &Framebuffer) -> Result<(), String> {
+        let rgb = self.filter.apply(framebuffer, &self.palette);
+        let width = framebuffer.width as usize;
+        let text = match self.braille {
+            true => braille(&rgb, width, self.palette.color(0), self.palette.color(1)),
+            false => half_blocks(&rgb, width),
+        };
+        let lines: Vec<String> = text.lines().map(String::from).collect();
+        if lines == self.lines {
+            return Ok(());
+        }
+
+        let mut out = String::new();
+        for (row, line) in lines.iter().enumerate() {
+            if self.lines.get(row) != Some(line) {
+                out.push_str(&format!("\x1b[{};1H{}", row + 1, line));
+            }
+        }
+        // Leaves the cursor below the screen for printed messages.
+        out.push_str(&format!("\x1b[{};1H", lines.len() + 1));
+
+        let mut stdout = io::stdout();
+        stdout.write_all(out.as_bytes()).map_err(|e| e.to_string())?;
+        stdout.flush().map_err(|e| e.to_string())?;
+        self.lines = lines;
+
+        return Ok(());
+    }
+
+    fn cycle_filter(&mut self) -> Option<String> {
+        self.filter.set_mode(self.filter.mode.next());
+
+        return Some(self.filter.mode.name());
+    }
+
+    fn is_animating(&self) -> bool {
+        return self.filter.is_animating();
+    }
+}
+
+impl Drop for TerminalDisplay {
+    fn drop(&mut self) {
+        print!("\x1b[0m\x1b[?25h");
+        let _ = io::stdout().flush();
+    }
+}
+
+/// 8-bit RGB pixels as lines of `▀`, the top pixel in the foreground and the
+/// bottom one in the background color, each line ending in a newline. Cells
+/// with two pixels of the same color are spaces.
+pub fn half_blocks(rgb: &[u8], width: usize) -> String {
+    let color = |x: usize, y: usize| {
+        let at = (x + y * width) * 3;
+        return [rgb[at], rgb[at + 1], rgb[at + 2]];
+    };
+    let height = rgb.len() / 3 / width;
+    let mut out = String::new();
+
+    for y in (0..height).step_by(2) {
+        let mut current = None;
+        for x in 0..width {
+            let colors = (color(x, y), color(x, (y + 1).min(height - 1)));
+            if current != Some(colors) {
+                let ([r, g, b], [r2, g2, b2]) = colors;
+                out.push_str(&format!("\x1b[38;2;{};{};{};48;2;{};{};{}m", r, g, b, r2, g2, b2));
+                current = Some(colors);
+            }
+            out.push(if colors.0 == colors.1 { ' ' } else { '▀' });
+        }
+        out.push_str("\x1b[0m\n");
+    }
+
+    return out;
+}
+
+/// 8-bit RGB pixels as lines of braille patterns with a dot for every pixel
+/// not in the background color.
+pub fn braille(rgb: &[u8], width: usize, background: u32, foreground: u32) -> String {
+    // The dot of each pixel of a 2x4 cell, by row.
+    const DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+    let background_rgb = [(background >> 16) as u8, (background >> 8) as u8, background as u8];
+    let height = rgb.len() / 3 / width;
+    let mut out = String::new();
+
+    for top in (0..height).step_by(4) {
+        out.push_str(&format!(
+            "\x1b[38;2;{};{};{};48;2;{};{};{}m",
+            (foreground >> 16) & 0xFF,
+            (foreground >> 8) & 0xFF,
+            foreground & 0xFF,
+            background_rgb[0],
+            background_rgb[1],
+            background_rgb[2]
+        ));
+        for left in (0..width).step_by(2) {
+            let mut pattern = 0;
+            for (row, dots) in DOTS.iter().enumerate() {
+                for (column, dot) in dots.iter().enumerate() {
+                    let (x, y) = (left + column, top + row);
+                    let at = (x + y * width) * 3;
+                    if x < width && y < height && rgb[at..at + 3] != background_rgb {
+                        pattern |= dot;
+                    }
+                }
+            }
+            out.push(std::char::from_u32(0x2800 + pattern).unwrap());
+        }
+        out.push_str("\x1b[0m\n");
+    }
+
+    return out;
+}
+
+/// Splits bytes read from a terminal into the keys pressed, by their SDL
+/// names, and whether Shift was held. Ctrl+C is reported as `Ctrl+C`.
+pub fn parse_keys(input: &[u8]) -> Vec<(String, bool)> {
+    let mut keys = Vec::new();
+    let mut position = 0;
+
+    while position < input.len() {
+        let byte = input[position];
+        position += 1;
+
+        let key = match byte {
+            0x1B => {
+                let (length, key) = escape_sequence(&input[position..]);
+                position += length;
+                key
+            }
+            0x03 => Some((String::from("Ctrl+C"), false)),
+            0x08 | 0x7F => Some((String::from("Backspace"), false)),
+            b'\r' | b'\n' => Some((String::from("Return"), false)),
+            b'\t' => Some((String::from("Tab"), false)),
+            b' ' => Some((String::from("Space"), false)),
+            b'A'..=b'Z' => Some(((byte as char).to_string(), true)),
+            0x21..=0x7E => Some(((byte as char).to_ascii_uppercase().to_string(), false)),
+            _ => None,
+        };
+        if let Some(key) = key {
+            keys.push(key);
+        }
+    }
+
+    return keys;
+}
+
+/// Decodes what follows an escape byte, returning how many bytes it took.
+/// An escape on its own is the Escape key.
+fn escape_sequence(input: &[u8]) -> (usize, Option<(String, bool)>) {
+    let introducer = match input.first() {
+        Some(b'[') | Some(b'O') => input[0],
+        _ => return (0, Some((String::from("Escape"), false))),
+    };
+    let end = match input.iter().skip(1).position(|byte| (0x40..=0x7E).contains(byte)) {
+        Some(end) => end + 2,
+        None => return (input.len(), None),
+    };
+
+    // Parameters such as `15` in `ESC [ 15 ~` or `1;2` in `ESC [ 1;2 A`,
+    // where a modifier of 2 is Shift.
+    let parameters = String::from_utf8_lossy(&input[1..end - 1]).to_string();
+    let mut numbers = parameters.split(';').map(|number| number.parse::<u8>().unwrap_or(1));
+    let number = numbers.next().unwrap_or(1);
+    let shift = numbers.next().map(|modifier| modifier.saturating_sub(1) & 1 != 0).unwrap_or(false);
+
+    let name = match (introducer, input[end - 1], number) {
+        (_, b'A', _) => "Up",
+        (_, b'B', _) => "Down",
+        (_, b'C', _) => "Right",
+        (_, b'D', _) => "Left",
+        (_, b'H', _) => "Home",
+        (_, b'F', _) => "End",
+        (_, b'P', _) => "F1",
+        (_, b'Q', _) => "F2",
+        (_, b'R', _) => "F3",
+        (_, b'S', _) => "F4",
+        (b'[', b'~', 2) => "Insert",
+        (b'[', b'~', 3) => "Delete",
+        (b'[', b'~', 5) => "PageUp",
+        (b'[', b'~', 6) => "PageDown",
+        (b'[', b'~', 11) => "F1",
+        (b'[', b'~', 12) => "F2",
+        (b'[', b'~', 13) => "F3",
+        (b'[', b'~', 14) => "F4",
+        (b'[', b'~', 15) => "F5",
+        (b'[', b'~', 17) => "F6",
+        (b'[', b'~', 18) => "F7",
+        (b'[', b'~', 19) => "F8",
+        (b'[', b'~', 20) => "F9",
+        (b'[', b'~', 21) => "F10",
+        (b'[', b'~', 23) => "F11",
+        (b'[', b'~', 24) => "F12",
+        _ => return (end, None),
+    };
+
+    return (end, Some((String::from(name), shift)));
+}
+
+/// Puts the terminal into raw mode, without echo or line buffering, until
+/// dropped.
+struct RawMode {
+    /// The settings to restore, as printed by `stty -g`.
+    saved: String,
+}
+
+impl RawMode {
+    fn new() -> Result<RawMode, String> {
+        let saved = stty(&["-g"])?;
+        // Keeps output processing, so printed messages still start on a new
+        // line, and Ctrl+C arrives as a key instead of killing the process.
+        stty(&["-icanon", "-echo", "-isig", "-ixon", "min", "1", "time", "0"])?;
+
+        return Ok(RawMode {
+            saved: saved.trim().to_string(),
+        });
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        if let Err(message) = stty(&[&self.saved]) {
+            eprintln!("Could not restore the terminal: {}", message);
+        }
+    }
+}
+
+fn stty(args: &[&str]) -> Result<String, String> {
+    let output = Process::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| format!("Could not run stty: {}", e))?;
+    if !output.status.success() {
+        return Err(format!(
+            "The keypad is read from a terminal, but stty failed: {}",
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+
+    return Ok(String::from_utf8_lossy(&output.stdout).to_string());
+}
+
+/// Reads the keypad and hotkeys from stdin. Key releases are emulated, see
+/// `FIRST_HOLD`.
+pub struct TerminalInput {
+    keymap: Keymap,
+    bytes: Receiver<Vec<u8>>,
+    /// The actions of the keys being held and when they are released.
+    held: HashMap<String, (Action, Instant)>,
+    _raw_mode: RawMode,
+}
+
+impl TerminalInput {
+    pub fn new(config: &Config) -> Result<TerminalInput, String> {
+        let keymap = Keymap::from_config(config)?;
+        let raw_mode = RawMode::new()?;
+
+        let (sender, bytes) = mpsc::channel();
+        thread::spawn(move || {
+            let mut stdin = io::stdin();
+            let mut buffer = [0; 64];
+            while let Ok(count) = stdin.read(&mut buffer) {
+                if count == 0 || sender.send(buffer[..count].to_vec()).is_err() {
+                    break;
+                }
+            }
+        });
+
+        return Ok(TerminalInput {
+            keymap,
+            bytes,
+            held: HashMap::new(),
+            _raw_mode: raw_mode,
+        });
+    }
+
+    fn press(&mut self, key: &str, shift: bool, keyboard: &mut Keyboard, commands: &mut Vec<Command>) {
+        let now = Instant::now();
+        let action = match self.keymap.action(key, shift) {
+            Some(action) => action,
+            None => return,
+        };
+
+        if let Some((_, release)) = self.held.get_mut(key) {
+            *release = now + REPEAT_HOLD;
+            match action {
+                Action::StepInstruction => commands.push(Command::StepInstruction),
+                Action::StepFrame => commands.push(Command::StepFrame),
+                _ => {}
+            }
+            return;
+        }
+        self.held.insert(key.to_string(), (action, now + FIRST_HOLD));
+
+        match action {
+            Action::Key(key) => keyboard.on_key_down(key),
+            Action::Quit => commands.push(Command::Quit),
+            Action::Pause => commands.push(Command::TogglePause),
+            Action::StepInstruction => commands.push(Command::StepInstruction),
+            Action::StepFrame => commands.push(Command::StepFrame),
+            Action::Rewind => commands.push(Command::Rewind(true)),
+            Action::Save(slot) => commands.push(Command::SaveState(slot)),
+            Action::Load(slot) => commands.push(Command::LoadState(slot)),
+            Action::Filter => commands.push(Command::CycleFilter),
+            Action::Screenshot => commands.push(Command::Screenshot),
+            Action::Capture => commands.push(Command::ToggleCapture),
+            // There is no window to switch and the keys are remapped in the
+            // config file instead.
+            Action::Fullscreen | Action::Remap => {}
+        }
+    }
+}
+
+impl Input for TerminalInput {
+    fn poll(&mut self, keyboard: &mut Keyboard) -> Vec<Command> {
+        let mut commands = Vec::new();
+
+        loop {
+            let bytes = match self.bytes.try_recv() {
+                Ok(bytes) => bytes,
+                Err(TryRecvError::Empty) => break,
+                Err(TryRecvError::Disconnected) => return vec![Command::Quit],
+            };
+            for (key, shift) in parse_keys(&bytes) {
+                if key == "Ctrl+C" {
+                    return vec![Command::Quit];
+                }
+                self.press(&key, shift, keyboard, &mut commands);
+            }
+        }
+
+        let now = Instant::now();
+        let released: Vec<String> = self
+            .held
+            .iter()
+            .filter(|(_, (_, release))| *release <= now)
+            .map(|(key, _)| key.clone())
+            .collect();
+        for key in released {
+            match self.held.remove(&key) {
+                Some((Action::Key(key), _)) => keyboard.on_key_up(key),
+                Some((Action::Rewind, _)) => commands.push(Command::Rewind(false)),
+                _ => {}
+            }
+        }
+
+        return commands;
+    }
+}
+
+/// Rings the terminal bell whenever a beep starts.
+pub struct Bell {
+    beeping: bool,
+}
+
+impl Bell {
+    pub fn new() -> Bell {
+        return Bell { beeping: false };
+    }
+}
+
+impl Default for Bell {
+    fn default() -> Bell {
+        return Bell::new();
+    }
+}
+
+impl Audio for Bell {
+    fn set_beeping(&mut self, beeping: bool) {
+        if beeping && !self.beeping {
+            print!("\x07");
+            let _ = io::stdout().flush();
+        }
+        self.beeping = beeping;
+    }
+}
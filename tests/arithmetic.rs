@@ -0,0 +1,52 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+
+/// Loads `vx` and `vy` into V0 and V1, or VF and V1 when `x` is F, and runs
+/// the 8XYN instruction `op` on them.
+fn run_op(x: u8, op: u8, vx: u8, vy: u8) -> Vec<u8> {
+    let program = [0x60 | x, vx, 0x61, vy, 0x80 | x, 0x10 | op];
+    let mut cpu = cpu_with_program(&program);
+    run_steps(&mut cpu, 3);
+
+    return cpu.v;
+}
+
+#[test]
+fn add_sets_carry() {
+    let v = run_op(0, 0x4, 0xFF, 0x02);
+    assert_eq!((v[0], v[0xF]), (0x01, 1));
+
+    let v = run_op(0, 0x4, 0x10, 0x02);
+    assert_eq!((v[0], v[0xF]), (0x12, 0));
+}
+
+#[test]
+fn sub_clears_vf_on_borrow_only() {
+    let v = run_op(0, 0x5, 0x05, 0x07);
+    assert_eq!((v[0], v[0xF]), (0xFE, 0));
+
+    let v = run_op(0, 0x5, 0x07, 0x07);
+    assert_eq!((v[0], v[0xF]), (0x00, 1));
+}
+
+#[test]
+fn reverse_sub_writes_vx() {
+    let v = run_op(0, 0x7, 0x05, 0xFE);
+    assert_eq!((v[0], v[1], v[0xF]), (0xF9, 0xFE, 1));
+
+    let v = run_op(0, 0x7, 0x07, 0x05);
+    assert_eq!((v[0], v[0xF]), (0xFE, 0));
+
+    let v = run_op(0, 0x7, 0x07, 0x07);
+    assert_eq!((v[0], v[0xF]), (0x00, 1));
+}
+
+#[test]
+fn flag_wins_over_the_result_in_vf() {
+    // The operands overwritten by the flag are loaded into VF itself.
+    assert_eq!(run_op(0xF, 0x4, 0xFF, 0x01)[0xF], 1);
+    assert_eq!(run_op(0xF, 0x5, 0x05, 0x01)[0xF], 1);
+    assert_eq!(run_op(0xF, 0x7, 0x05, 0x06)[0xF], 1);
+    assert_eq!(run_op(0xF, 0x7, 0x06, 0x05)[0xF], 0);
+}
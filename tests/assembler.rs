@@ -0,0 +1,88 @@
+use rust_chip::assembler::{assemble, assemble_file};
+use rust_chip::disasm::disassemble;
+use rust_chip::opcodes::{self, OPCODES};
+use std::fs;
+
+#[test]
+fn assembles_classic_mnemonics() {
+    let rom = assemble("CLS\nLD V3, 0x1F\nDRW V0, V1, 5\nSAVE V1 - V3\nLD I, long 0x1234\nJP V0, 0x300", 0x200).unwrap();
+
+    assert_eq!(
+        rom,
+        vec![0x00, 0xE0, 0x63, 0x1F, 0xD0, 0x15, 0x51, 0x32, 0xF0, 0x00, 0x12, 0x34, 0xB3, 0x00]
+    );
+}
+
+#[test]
+fn resolves_labels_aliases_and_constants() {
+    let source = "
+        :alias x v2
+        :const SPEED 4
+        : main
+            ld x, SPEED   # Octo style comment
+            ld i, sprite
+            call draw
+            jp main
+        draw: drw x, x, 2
+            ret
+        sprite:
+            db 0b11110000, %10010000
+            0x3C 0x42
+            dw draw
+    ";
+    let rom = assemble(source, 0x200).unwrap();
+
+    assert_eq!(
+        rom,
+        vec![
+            0x62, 0x04, 0xA2, 0x0C, 0x22, 0x08, 0x12, 0x00, 0xD2, 0x22, 0x00, 0xEE, 0xF0, 0x90, 0x3C, 0x42, 0x02, 0x08
+        ]
+    );
+}
+
+#[test]
+fn reports_errors_with_line_numbers() {
+    assert_eq!(assemble("CLS\nFOO V1", 0x200).unwrap_err(), "input:2: unknown instruction 'FOO'");
+    assert_eq!(
+        assemble("LD V1, 0x100", 0x200).unwrap_err(),
+        "input:1: value 0x100 does not fit in 'nn'"
+    );
+    assert_eq!(assemble("JP nowhere", 0x200).unwrap_err(), "input:1: unknown label or constant 'nowhere'");
+    assert_eq!(assemble("a:\na:", 0x200).unwrap_err(), "input:2: label 'a' is defined twice");
+    assert!(assemble("SKP 5", 0x200).unwrap_err().contains("invalid operands"));
+}
+
+#[test]
+fn includes_files_relative_to_the_source() {
+    let directory = std::env::temp_dir().join(format!("rust-chip-assembler-{}", std::process::id()));
+    fs::create_dir_all(directory.join("lib")).unwrap();
+    fs::write(directory.join("main.asm"), "JP start\n:include \"lib/data.asm\"\nstart: LD I, data\n").unwrap();
+    fs::write(directory.join("lib/data.asm"), "data: db 0xAA\n").unwrap();
+
+    let rom = assemble_file(&directory.join("main.asm"), 0x200).unwrap();
+    fs::remove_dir_all(&directory).unwrap();
+
+    assert_eq!(rom, vec![0x12, 0x03, 0xAA, 0xA2, 0x02]);
+}
+
+#[test]
+fn every_opcode_round_trips() {
+    for entry in OPCODES.iter() {
+        let text = opcodes::format(entry, entry.pattern | 0x0120 & !entry.mask, 0xBEEF, None);
+        let rom = assemble(&text, 0x200).unwrap();
+        let opcode = u16::from_be_bytes([rom[0], rom[1]]);
+
+        assert_eq!(opcodes::decode(opcode).unwrap().op, entry.op, "{}", text);
+        assert_eq!(rom.len() as u16, entry.size(), "{}", text);
+    }
+}
+
+#[test]
+fn reassembles_disassembled_roms() {
+    for path in ["roms/BC_test.ch8", "roms/BRIX.ch8", "roms/BLITZ.ch8"].iter() {
+        let rom = fs::read(path).unwrap();
+        let listing = disassemble(&rom, 0x200).to_string();
+
+        assert_eq!(assemble(&listing, 0x200).unwrap(), rom, "{}", path);
+    }
+}
@@ -0,0 +1,156 @@
+use rust_chip::capture::{self, Recorder, Settings};
+use rust_chip::cpu::Cpu;
+use std::fs;
+use std::path::PathBuf;
+
+fn temp_path(name: &str) -> PathBuf {
+    return std::env::temp_dir().join(format!("rust-chip-{}-{}", std::process::id(), name));
+}
+
+/// Decodes the LZW data of a GIF image, for checking the encoder.
+fn lzw_decode(minimum_size: u32, data: &[u8]) -> Vec<u8> {
+    let clear = 1u32 << minimum_size;
+    let mut table: Vec<Vec<u8>> = Vec::new();
+    let mut width = minimum_size + 1;
+    let mut previous: Option<Vec<u8>> = None;
+    let mut out = Vec::new();
+    let mut position = 0;
+
+    loop {
+        let code = (0..width).fold(0, |code, bit| {
+            let at = position + bit as usize;
+            code | (((data[at / 8] >> (at % 8)) & 1) as u32) << bit
+        });
+        position += width as usize;
+
+        if code == clear {
+            table = (0..clear + 2).map(|index| vec![index as u8]).collect();
+            width = minimum_size + 1;
+            previous = None;
+            continue;
+        }
+        if code == clear + 1 {
+            return out;
+        }
+
+        let entry = match (table.get(code as usize), &previous) {
+            (Some(entry), _) => entry.clone(),
+            (None, Some(previous)) => [previous.clone(), vec![previous[0]]].concat(),
+            (None, None) => panic!("invalid code {}", code),
+        };
+        if let Some(previous) = previous {
+            if table.len() < 4096 {
+                table.push([previous, vec![entry[0]]].concat());
+            }
+        }
+        if table.len() == 1 << width && width < 12 {
+            width += 1;
+        }
+
+        out.extend_from_slice(&entry);
+        previous = Some(entry);
+    }
+}
+
+/// The palette indexes of the first image of a GIF.
+fn first_gif_frame(gif: &[u8]) -> (u16, Vec<u8>) {
+    // Header, logical screen and a 16-color table, then the loop extension.
+    let mut at = 13 + 16 * 3 + 19;
+    assert_eq!(&gif[at..at + 2], [0x21, 0xF9]);
+    let delay = u16::from_le_bytes([gif[at + 4], gif[at + 5]]);
+    at += 8 + 10;
+
+    let minimum_size = gif[at] as u32;
+    at += 1;
+    let mut data = Vec::new();
+    while gif[at] != 0 {
+        let length = gif[at] as usize;
+        data.extend_from_slice(&gif[at + 1..at + 1 + length]);
+        at += 1 + length;
+    }
+
+    return (delay, lzw_decode(minimum_size, &data));
+}
+
+#[test]
+fn numbers_captures_next_to_the_rom() {
+    let rom = temp_path("GAME.ch8");
+    let first = capture::next_path(&rom, "png");
+    assert_eq!(first.file_name().unwrap(), &*format!("rust-chip-{}-GAME-1.png", std::process::id()));
+
+    fs::write(&first, b"").unwrap();
+    let second = capture::next_path(&rom, "png");
+    fs::remove_file(&first).unwrap();
+    assert!(second.to_string_lossy().ends_with("GAME-2.png"));
+}
+
+#[test]
+fn gif_sizes_fit_up_to_the_largest_scale() {
+    let path = temp_path("large.gif");
+    let mut settings = Settings::new();
+    settings.scale = capture::MAX_SCALE;
+
+    drop(Recorder::create(&path, &settings).unwrap());
+    let gif = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    let width = u16::from_le_bytes([gif[6], gif[7]]) as usize;
+    assert_eq!(width, 128 * capture::MAX_SCALE);
+    assert!(128 * (capture::MAX_SCALE + 1) > u16::MAX as usize);
+}
+
+#[test]
+fn screenshots_are_scaled() {
+    let mut cpu = Cpu::new();
+    cpu.framebuffer.set_pixel(0, 0, 1);
+    let path = temp_path("shot.png");
+
+    let mut settings = Settings::new();
+    settings.scale = 3;
+    capture::save_screenshot(&cpu.framebuffer, &settings, &path).unwrap();
+    let png = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // The width and height in the IHDR chunk.
+    assert_eq!(png[16..24], [0, 0, 0, 192, 0, 0, 0, 96]);
+}
+
+#[test]
+fn records_gifs_and_raw_video() {
+    let mut cpu = Cpu::new();
+    let mut settings = Settings::new();
+    settings.scale = 1;
+
+    let gif = temp_path("capture.gif");
+    let raw = temp_path("capture.rgb");
+    let mut recorders = vec![Recorder::create(&gif, &settings).unwrap(), Recorder::create(&raw, &settings).unwrap()];
+
+    for frame in 0..10 {
+        cpu.framebuffer.set_pixel(frame, 0, 1);
+        for recorder in recorders.iter_mut() {
+            recorder.record(&cpu).unwrap();
+        }
+    }
+    for recorder in recorders {
+        assert_eq!(recorder.size(), (128, 64));
+        assert_eq!(recorder.frames, 10);
+        recorder.finish().unwrap();
+    }
+
+    // Lo-res pixels are doubled to fill the hi-res sized frames.
+    let raw = fs::read(&raw).unwrap();
+    assert_eq!(raw.len(), 10 * 128 * 64 * 3);
+    assert_eq!(raw[..9], [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0, 0, 0]);
+
+    // A 60 Hz frame rounds to 2/100 s, the shortest delay browsers play at
+    // full speed.
+    let gif = fs::read(&gif).unwrap();
+    assert_eq!(&gif[..6], b"GIF89a");
+    assert_eq!(gif.last(), Some(&0x3B));
+    let (delay, pixels) = first_gif_frame(&gif);
+    assert_eq!(delay, 2);
+    assert_eq!(pixels.len(), 128 * 64);
+    assert_eq!(pixels[..4], [1, 1, 0, 0]);
+    assert_eq!(pixels[128..132], [1, 1, 0, 0]);
+    assert_eq!(pixels.iter().filter(|pixel| **pixel == 1).count(), 4);
+}
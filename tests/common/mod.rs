@@ -0,0 +1,17 @@
+#![allow(dead_code)]
+
+use rust_chip::cpu::{Cpu, PROGRAM_START};
+
+pub fn cpu_with_program(program: &[u8]) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.load_sprites();
+    cpu.memory[PROGRAM_START..PROGRAM_START + program.len()].copy_from_slice(program);
+
+    return cpu;
+}
+
+pub fn run_steps(cpu: &mut Cpu, steps: usize) {
+    for _ in 0..steps {
+        cpu.step().unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::debugger::{Breakpoint, Condition, DebugCommand, Debugger};
+
+#[test]
+fn parses_commands() {
+    assert_eq!(Debugger::parse("s"), Ok(DebugCommand::Step(1)));
+    assert_eq!(Debugger::parse("step 10"), Ok(DebugCommand::Step(10)));
+    assert_eq!(Debugger::parse("b 0x204"), Ok(DebugCommand::Add(Breakpoint::Address(0x204))));
+    assert_eq!(Debugger::parse("watch $300"), Ok(DebugCommand::Add(Breakpoint::Watch(0x300))));
+    assert_eq!(Debugger::parse("m 0x200"), Ok(DebugCommand::Memory(0x200, 16)));
+    assert!(Debugger::parse("b").is_err());
+    assert!(Debugger::parse("jump 0x200").is_err());
+    assert!(Condition::parse("V3 = 1").is_err());
+    assert!(Condition::parse("VG == 1").is_err());
+}
+
+#[test]
+fn breaks_at_address_and_resumes_past_it() {
+    // 6001 6102 1200: loop forever setting V0 and V1.
+    let mut cpu = cpu_with_program(&[0x60, 0x01, 0x61, 0x02, 0x12, 0x00]);
+    let mut debugger = Debugger::new();
+    debugger.add(Breakpoint::Address(0x202));
+
+    assert_eq!(debugger.check_before(&cpu), None);
+    run_steps(&mut cpu, 1);
+    assert!(debugger.check_before(&cpu).is_some());
+
+    // Continuing from the breakpoint executes it instead of stopping again.
+    debugger.resume(&cpu);
+    assert_eq!(debugger.check_before(&cpu), None);
+    run_steps(&mut cpu, 2);
+    assert_eq!(cpu.pc, 0x200);
+    assert_eq!(debugger.check_before(&cpu), None);
+    run_steps(&mut cpu, 1);
+    assert!(debugger.check_before(&cpu).is_some());
+}
+
+#[test]
+fn watchpoint_fires_after_store() {
+    // A300 6042 F033: store the BCD of 0x42 (066) at 0x300..0x302.
+    let mut cpu = cpu_with_program(&[0xA3, 0x00, 0x60, 0x42, 0xF0, 0x33]);
+    let mut debugger = Debugger::new();
+    debugger.add(Breakpoint::Watch(0x302));
+
+    run_steps(&mut cpu, 2);
+    assert_eq!(debugger.check_after(&cpu, 0x202), None);
+    run_steps(&mut cpu, 1);
+    let reason = debugger.check_after(&cpu, 0x204).unwrap();
+    assert!(reason.contains("0x204"));
+    assert_eq!(cpu.memory[0x302], 6);
+}
+
+#[test]
+fn condition_breaks_when_it_becomes_true() {
+    // 7001 1200: increment V0 forever.
+    let mut cpu = cpu_with_program(&[0x70, 0x01, 0x12, 0x00]);
+    let mut debugger = Debugger::new();
+    debugger.add(Breakpoint::Condition(Condition::parse("V0 >= 3").unwrap()));
+
+    let mut hits = 0;
+    for _ in 0..20 {
+        if debugger.check_before(&cpu).is_some() {
+            hits += 1;
+            assert_eq!(cpu.v[0], 3);
+        }
+        run_steps(&mut cpu, 1);
+    }
+
+    // The condition stays true afterwards but only breaks once.
+    assert_eq!(hits, 1);
+}
+
+#[test]
+fn deletes_breakpoints() {
+    let mut debugger = Debugger::new();
+    debugger.add(Breakpoint::Address(0x200));
+    debugger.add(Breakpoint::Watch(0x300));
+
+    assert!(debugger.delete(2).is_err());
+    debugger.delete(0).unwrap();
+    assert_eq!(debugger.breakpoints, vec![Breakpoint::Watch(0x300)]);
+    assert_eq!(debugger.list(), "0: watch 0x300");
+}
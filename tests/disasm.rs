@@ -0,0 +1,69 @@
+use rust_chip::disasm::disassemble;
+use rust_chip::opcodes::{self, OPCODES};
+
+#[test]
+fn formats_operands() {
+    let listing = disassemble(&[0x63, 0x1F, 0xD0, 0x15, 0x00, 0xFD], 0x200);
+    let text: Vec<&str> = listing.lines.iter().map(|line| line.text.as_str()).collect();
+
+    assert_eq!(text, vec!["LD V3, 0x1F", "DRW V0, V1, 5", "EXIT"]);
+}
+
+#[test]
+fn separates_code_from_data() {
+    // A206 D005 1204, then a five byte sprite.
+    let rom = [0xA2, 0x06, 0xD0, 0x05, 0x12, 0x04, 0xF0, 0x90, 0x90, 0x90, 0xF0];
+    let listing = disassemble(&rom, 0x200);
+
+    assert_eq!(listing.lines.len(), 3 + 5);
+    assert_eq!(listing.lines[0].text, "LD I, data_206");
+    assert_eq!(listing.lines[2].text, "JP L204");
+    assert_eq!(listing.lines[3].text, "db 0xF0");
+    assert_eq!(listing.labels.get(&0x204).map(|label| label.as_str()), Some("L204"));
+
+    let text = listing.to_string();
+    assert!(text.contains("data_206:\n    db 0xF0"));
+    assert!(text.contains("; 0x207  90         #..#...."));
+}
+
+#[test]
+fn follows_calls_and_skips() {
+    // CALL 0x206, a skip over an XO-CHIP long load, EXIT, then the subroutine.
+    let rom = [
+        0x22, 0x0C, 0x30, 0x01, 0xF0, 0x00, 0x03, 0x00, 0x00, 0xFD, 0xFF, 0xFF, 0x00, 0xFF, 0x00, 0xEE,
+    ];
+    let listing = disassemble(&rom, 0x200);
+    let text: Vec<&str> = listing.lines.iter().map(|line| line.text.as_str()).collect();
+
+    assert_eq!(
+        text,
+        vec!["CALL sub_20C", "SE V0, 0x01", "LD I, long 0x0300", "EXIT", "db 0xFF", "db 0xFF", "HIGH", "RET"]
+    );
+}
+
+#[test]
+fn stops_at_the_end_of_the_address_space() {
+    // CLS / JP 0x234, then a byte that would be at 0x10000.
+    let listing = disassemble(&[0x00, 0xE0, 0x12, 0x34, 0xAA], 0xFFFC);
+    let text: Vec<&str> = listing.lines.iter().map(|line| line.text.as_str()).collect();
+
+    assert_eq!(text, vec!["CLS", "JP 0x234"]);
+    assert_eq!(listing.lines[1].address, 0xFFFE);
+}
+
+#[test]
+fn every_opcode_has_a_unique_pattern() {
+    for entry in OPCODES.iter() {
+        let decoded = opcodes::decode(entry.pattern).unwrap();
+        assert_eq!(decoded.op, entry.op, "{} is shadowed by {}", entry.syntax, decoded.syntax);
+    }
+}
+
+#[test]
+fn disassembles_bc_test() {
+    let rom = std::fs::read("roms/BC_test.ch8").unwrap();
+    let listing = disassemble(&rom, 0x200);
+
+    assert_eq!(listing.lines.iter().map(|line| line.bytes.len()).sum::<usize>(), rom.len());
+    assert!(listing.lines.iter().all(|line| !line.text.is_empty()));
+}
@@ -0,0 +1,129 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::cpu::{Cpu, PROGRAM_START, STACK_DEPTH};
+use rust_chip::error::Chip8Error;
+use rust_chip::quirks::Quirks;
+use std::path::Path;
+
+#[test]
+fn reports_unknown_opcodes_with_their_address() {
+    // LD V0, 0x01 / 0x8008 is not an instruction
+    let mut cpu = cpu_with_program(&[0x60, 0x01, 0x80, 0x08]);
+
+    run_steps(&mut cpu, 1);
+
+    assert_eq!(
+        cpu.step(),
+        Err(Chip8Error::UnknownOpcode {
+            opcode: 0x8008,
+            address: 0x202
+        })
+    );
+    assert_eq!(cpu.pc, 0x202);
+}
+
+#[test]
+fn reports_return_with_empty_stack() {
+    let mut cpu = cpu_with_program(&[0x00, 0xEE]);
+
+    assert_eq!(cpu.step(), Err(Chip8Error::StackUnderflow { address: 0x200 }));
+}
+
+#[test]
+fn reports_stack_overflow() {
+    // CALL 0x200, forever
+    let mut cpu = cpu_with_program(&[0x22, 0x00]);
+
+    run_steps(&mut cpu, STACK_DEPTH);
+
+    assert_eq!(cpu.step(), Err(Chip8Error::StackOverflow { address: 0x200 }));
+}
+
+#[test]
+fn reports_i_indexed_accesses_past_the_end_of_memory() {
+    // LD I, 0xFFE / LD [I], V3
+    let mut cpu = cpu_with_program(&[0xAF, 0xFE, 0xF3, 0x55]);
+
+    run_steps(&mut cpu, 1);
+
+    assert_eq!(
+        cpu.step(),
+        Err(Chip8Error::MemoryOutOfBounds {
+            address: 0x202,
+            target: 0x1001
+        })
+    );
+}
+
+#[test]
+fn reports_sprites_past_the_end_of_memory() {
+    // LD I, 0xFFC / DRW V0, V0, 8
+    let mut cpu = cpu_with_program(&[0xAF, 0xFC, 0xD0, 0x08]);
+
+    run_steps(&mut cpu, 1);
+
+    assert!(matches!(cpu.step(), Err(Chip8Error::MemoryOutOfBounds { .. })));
+}
+
+/// An XO-CHIP CPU with `program` in the last bytes of its 64 KiB and `pc` on it.
+fn cpu_at_end_of_memory(program: &[u8]) -> Cpu {
+    let mut cpu = Cpu::new();
+    cpu.quirks = Quirks::xo_chip();
+    cpu.load_rom(&[]).unwrap();
+    let start = 0x10000 - program.len();
+    cpu.memory[start..].copy_from_slice(program);
+    cpu.pc = start as u16;
+
+    return cpu;
+}
+
+#[test]
+fn reports_running_past_the_end_of_the_address_space() {
+    // LD V0, 0x01
+    let mut cpu = cpu_at_end_of_memory(&[0x60, 0x01]);
+
+    assert_eq!(
+        cpu.step(),
+        Err(Chip8Error::MemoryOutOfBounds {
+            address: 0xFFFE,
+            target: 0x10000
+        })
+    );
+    assert_eq!(cpu.pc, 0xFFFE);
+}
+
+#[test]
+fn reports_skips_and_calls_past_the_end_of_the_address_space() {
+    // SE V0, 0x00 / CALL 0x200 / LD I, long address
+    for program in [[0x30, 0x00], [0x22, 0x00], [0xF0, 0x00]].iter() {
+        let mut cpu = cpu_at_end_of_memory(program);
+
+        assert!(matches!(cpu.step(), Err(Chip8Error::MemoryOutOfBounds { address: 0xFFFE, .. })));
+        assert_eq!(cpu.pc, 0xFFFE);
+    }
+}
+
+#[test]
+fn reports_roms_that_do_not_fit() {
+    let path = std::env::temp_dir().join("rust-chip-too-large.ch8");
+    std::fs::write(&path, vec![0; 0x1000 - PROGRAM_START + 1]).unwrap();
+
+    let result = Cpu::new().load_program(&path);
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        result,
+        Err(Chip8Error::RomTooLarge {
+            size: 0xE01,
+            available: 0xE00
+        })
+    );
+}
+
+#[test]
+fn reports_missing_roms() {
+    let result = Cpu::new().load_program(Path::new("roms/does-not-exist.ch8"));
+
+    assert!(matches!(result, Err(Chip8Error::RomUnreadable { .. })));
+}
@@ -0,0 +1,76 @@
+use rust_chip::filter::{Filter, Mode, BLEND_FRAMES};
+use rust_chip::framebuffer::Framebuffer;
+use rust_chip::palette::Palette;
+
+/// The red channel of the pixel at (x, 0).
+fn red(rgb: &[u8], x: usize) -> u8 {
+    return rgb[x * 3];
+}
+
+#[test]
+fn or_keeps_pixels_of_the_previous_frame() {
+    let palette = Palette::new();
+    let mut filter = Filter::new(Mode::Or);
+    let mut framebuffer = Framebuffer::new();
+
+    framebuffer.set_pixel(0, 0, 1);
+    filter.apply(&framebuffer, &palette);
+
+    // The sprite is erased and drawn one pixel further.
+    framebuffer.set_pixel(0, 0, 1);
+    framebuffer.set_pixel(1, 0, 1);
+    let rgb = filter.apply(&framebuffer, &palette);
+    assert_eq!((red(&rgb, 0), red(&rgb, 1)), (0xFF, 0xFF));
+
+    let rgb = filter.apply(&framebuffer, &palette);
+    assert_eq!((red(&rgb, 0), red(&rgb, 1)), (0x00, 0xFF));
+}
+
+#[test]
+fn blend_averages_the_last_frames() {
+    let palette = Palette::new();
+    let mut filter = Filter::new(Mode::Blend(2));
+    let mut framebuffer = Framebuffer::new();
+
+    filter.apply(&framebuffer, &palette);
+    framebuffer.set_pixel(0, 0, 1);
+    assert_eq!(red(&filter.apply(&framebuffer, &palette), 0), 0x7F);
+    assert_eq!(red(&filter.apply(&framebuffer, &palette), 0), 0xFF);
+}
+
+#[test]
+fn decay_fades_pixels_out() {
+    let palette = Palette::new();
+    let mut filter = Filter::new(Mode::Decay);
+    let mut framebuffer = Framebuffer::new();
+
+    framebuffer.set_pixel(0, 0, 1);
+    assert_eq!(red(&filter.apply(&framebuffer, &palette), 0), 0xFF);
+
+    framebuffer.set_pixel(0, 0, 1);
+    let mut last = 0xFF;
+    for _ in 0..5 {
+        let glow = red(&filter.apply(&framebuffer, &palette), 0);
+        assert!(glow < last);
+        last = glow;
+    }
+    for _ in 0..30 {
+        last = red(&filter.apply(&framebuffer, &palette), 0);
+    }
+    assert_eq!(last, 0);
+}
+
+#[test]
+fn modes_by_name() {
+    assert_eq!(Mode::from_name("blend").unwrap(), Mode::Blend(BLEND_FRAMES));
+    assert_eq!(Mode::from_name("Blend:5").unwrap(), Mode::Blend(5));
+    assert!(Mode::from_name("blend:0").is_err());
+    assert!(Mode::from_name("scanlines").is_err());
+
+    let mut mode = Mode::Off;
+    for _ in 0..4 {
+        mode = mode.next();
+        assert_eq!(Mode::from_name(&mode.name()).unwrap(), mode);
+    }
+    assert_eq!(mode, Mode::Off);
+}
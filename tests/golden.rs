@@ -0,0 +1,65 @@
+use rust_chip::cpu::Cpu;
+use rust_chip::harness::{self, Stop};
+use rust_chip::image;
+use rust_chip::quirks::Quirks;
+use std::env;
+use std::fs;
+use std::path::Path;
+
+// Only BC_test ships with the repository, so it is the only ROM checked
+// against a golden image. Timendus' chip8-test-suite, corax+ and flags are
+// not bundled; the opcodes, flags and quirks they exercise are unit tested in
+// arithmetic.rs, quirks.rs, schip.rs and xochip.rs instead. Other test ROMs
+// can be checked with `rust-chip headless <ROM> --golden <PBM>`.
+//
+// UPDATE_GOLDEN=1 writes whatever the emulator draws, so look at a new image
+// before committing it.
+
+/// Runs `rom` until it halts and compares the screen with `tests/golden/<name>.pbm`.
+fn check(rom: &str, quirks: Quirks, name: &str) {
+    let rom = Path::new(rom);
+    assert!(rom.exists(), "{} is missing", rom.display());
+
+    let mut cpu = Cpu::new();
+    cpu.quirks = quirks;
+    cpu.speed = 30;
+    cpu.load_sprites();
+    cpu.load_program(rom).unwrap();
+
+    let (frames, stop) = harness::run(&mut cpu, 600).unwrap();
+    assert!(stop != Stop::Frames, "{} was still running after {} frames", name, frames);
+
+    let golden = Path::new("tests/golden").join(format!("{}.pbm", name));
+    if env::var_os("UPDATE_GOLDEN").is_some() {
+        fs::write(&golden, image::pbm(&cpu.framebuffer)).unwrap();
+        return;
+    }
+    if let Err(message) = harness::compare(&cpu, &golden) {
+        panic!("{}", message);
+    }
+}
+
+#[test]
+fn bc_test_shows_bon() {
+    check("roms/BC_test.ch8", Quirks::chip48(), "BC_test");
+}
+
+#[test]
+fn images_encode_the_screen() {
+    let mut cpu = Cpu::new();
+    cpu.framebuffer.set_pixel(1, 0, 1);
+    cpu.framebuffer.set_pixel(63, 31, 1);
+
+    let (width, height, pixels) = image::parse_pbm(&image::pbm(&cpu.framebuffer)).unwrap();
+    assert_eq!((width, height), (64, 32));
+    assert_eq!(pixels.iter().filter(|pixel| **pixel).count(), 2);
+    assert!(pixels[1] && pixels[64 * 32 - 1]);
+
+    let png = image::png(&cpu.framebuffer, &rust_chip::palette::Palette::new(), 1);
+    assert_eq!(&png[..8], b"\x89PNG\r\n\x1a\n");
+    assert_eq!(&png[png.len() - 12..], b"\0\0\0\0IEND\xAE\x42\x60\x82");
+
+    let hash = image::hash(&cpu.framebuffer);
+    cpu.framebuffer.set_pixel(1, 0, 1);
+    assert!(image::hash(&cpu.framebuffer) != hash);
+}
@@ -0,0 +1,58 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::cpu::Cpu;
+use std::path::Path;
+
+#[test]
+fn draws_font_sprite_without_a_window() {
+    // LD V0, 0x0 / LD F, V0 / DRW V0, V0, 5
+    let mut cpu = cpu_with_program(&[0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05]);
+
+    run_steps(&mut cpu, 3);
+
+    // The top row of the "0" glyph is 0xF0.
+    for x in 0..4 {
+        assert!(cpu.framebuffer.get_pixel(x, 0));
+    }
+    assert!(!cpu.framebuffer.get_pixel(4, 0));
+    assert!(cpu.framebuffer.dirty);
+    assert_eq!(cpu.v[0xF], 0);
+}
+
+#[test]
+fn sets_vf_when_a_sprite_erases_pixels() {
+    // LD V0, 0x0 / LD F, V0 / DRW V0, V0, 5 / DRW V0, V0, 5
+    let mut cpu = cpu_with_program(&[0x60, 0x00, 0xF0, 0x29, 0xD0, 0x05, 0xD0, 0x05]);
+
+    run_steps(&mut cpu, 4);
+
+    assert_eq!(cpu.v[0xF], 1);
+    assert!(cpu.framebuffer.pixels.iter().all(|&pixel| pixel == 0));
+}
+
+#[test]
+fn runs_rom_headless() {
+    let mut cpu = Cpu::new();
+    cpu.load_program(Path::new("roms/BC_test.ch8")).unwrap();
+
+    for _ in 0..1000 {
+        cpu.cycle().unwrap();
+    }
+
+    assert!(cpu.framebuffer.pixels.iter().any(|&pixel| pixel > 0));
+}
+
+#[test]
+fn sound_timer_counts_down_at_timer_rate() {
+    // LD V1, 0x03 / LD ST, V1 / JP 0x204
+    let mut cpu = cpu_with_program(&[0x61, 0x03, 0xF1, 0x18, 0x12, 0x04]);
+
+    run_steps(&mut cpu, 2);
+    assert_eq!(cpu.sound_timer, 3);
+
+    for expected in [2, 1, 0, 0].iter() {
+        cpu.update_timers();
+        assert_eq!(cpu.sound_timer, *expected);
+    }
+}
@@ -0,0 +1,78 @@
+use rust_chip::config::Config;
+use rust_chip::keymap::{Action, Keymap};
+use std::fs;
+
+#[test]
+fn defaults_to_the_qwerty_keypad() {
+    let keymap = Keymap::new();
+
+    assert_eq!(keymap.action("X", false), Some(Action::Key(0x0)));
+    assert_eq!(keymap.action("4", false), Some(Action::Key(0xC)));
+    assert_eq!(keymap.action("v", true), Some(Action::Key(0xF)));
+    assert_eq!(keymap.action("F1", false), Some(Action::Load(1)));
+    assert_eq!(keymap.action("F1", true), Some(Action::Save(1)));
+    assert_eq!(keymap.action("F5", false), Some(Action::Pause));
+    assert_eq!(keymap.action("Y", false), None);
+}
+
+#[test]
+fn config_overrides_keys_and_hotkeys() {
+    // An AZERTY keypad, with the arrows as a second set of directions.
+    let config = Config::parse(
+        "[keys]\nkey4 = A\nkey5 = Z, Up\nkey7 = Q\nkeyA = W\n[hotkeys]\npause = Space\nsave2 = Shift+S",
+    )
+    .unwrap();
+    let keymap = Keymap::from_config(&config).unwrap();
+
+    assert_eq!(keymap.action("A", false), Some(Action::Key(0x4)));
+    assert_eq!(keymap.action("up", false), Some(Action::Key(0x5)));
+    assert_eq!(keymap.action("Q", false), Some(Action::Key(0x7)));
+    assert_eq!(keymap.action("W", false), Some(Action::Key(0xA)));
+    assert_eq!(keymap.keys(Action::Key(0x5)), vec!["up", "z"]);
+    assert_eq!(keymap.action("Space", false), Some(Action::Pause));
+    assert_eq!(keymap.action("P", false), None);
+    assert_eq!(keymap.action("S", true), Some(Action::Save(2)));
+    assert_eq!(keymap.action("S", false), Some(Action::Key(0x8)));
+
+    for bad in ["[keys]\nkeyG = A", "[keys]\nquit = A", "[hotkeys]\nkey1 = A", "[hotkeys]\nsave5 = A"].iter() {
+        assert!(Keymap::from_config(&Config::parse(bad).unwrap()).is_err(), "{}", bad);
+    }
+}
+
+#[test]
+fn saves_remapped_keys_into_the_config_file() {
+    let path = std::env::temp_dir().join("rust-chip-keymap.ini");
+    fs::write(&path, "# mine\n[keys]\nkey0 = B\n[quirks]\nprofile = vip\n").unwrap();
+
+    let mut keymap = Keymap::new();
+    keymap.bind(Action::Key(0), &["Space"]);
+    Config::write_section(&path, "keys", &keymap.keypad_entries()).unwrap();
+    let text = fs::read_to_string(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    assert!(text.starts_with("# mine\n[quirks]\nprofile = vip\n"));
+    let config = Config::parse(&text).unwrap();
+    assert_eq!(config.get("quirks", "profile"), Some("vip"));
+    assert_eq!(Keymap::from_config(&config).unwrap(), keymap);
+}
+
+#[test]
+fn gamepads_use_the_shared_and_their_own_profile() {
+    let config = Config::parse(
+        "[gamepad]\nkey5 = a, rightshoulder\nsave1 = leftshoulder\n[gamepad.xbox 360 controller]\nkey5 = b\n",
+    )
+    .unwrap();
+
+    let generic = Keymap::gamepad_from_config(&config, "8BitDo Pro 2").unwrap();
+    assert_eq!(generic.action("dpup", false), Some(Action::Key(0x2)));
+    assert_eq!(generic.action("rightshoulder", false), Some(Action::Key(0x5)));
+    assert_eq!(generic.action("leftshoulder", false), Some(Action::Save(1)));
+    assert_eq!(generic.action("start", false), Some(Action::Pause));
+
+    let xbox = Keymap::gamepad_from_config(&config, "Xbox 360 Controller").unwrap();
+    assert_eq!(xbox.action("b", false), Some(Action::Key(0x5)));
+    assert_eq!(xbox.action("a", false), None);
+    assert_eq!(xbox.action("dpleft", false), Some(Action::Key(0x4)));
+
+    assert!(Keymap::gamepad_from_config(&Config::parse("[gamepad]\nkey10 = a").unwrap(), "pad").is_err());
+}
@@ -0,0 +1,85 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::chip8::Chip8;
+use rust_chip::cpu::Cpu;
+use rust_chip::frontend::{Headless, Scripted};
+use rust_chip::keyboard::{HostKeys, Keyboard, Source};
+
+#[test]
+fn key_0_can_be_pressed() {
+    // 6000 E09E 6101 6202: skip setting V1 while key 0 is held.
+    let program = [0x60, 0x00, 0xE0, 0x9E, 0x61, 0x01, 0x62, 0x02];
+    let mut cpu = cpu_with_program(&program);
+    cpu.keyboard.on_key_down(0x0);
+
+    run_steps(&mut cpu, 3);
+    assert_eq!((cpu.v[1], cpu.v[2]), (0, 2));
+}
+
+#[test]
+fn exa1_skips_while_the_key_is_up() {
+    // 6007 E0A1 6101 6202
+    let program = [0x60, 0x07, 0xE0, 0xA1, 0x61, 0x01, 0x62, 0x02];
+
+    let mut cpu = cpu_with_program(&program);
+    run_steps(&mut cpu, 3);
+    assert_eq!((cpu.v[1], cpu.v[2]), (0, 2));
+
+    let mut cpu = cpu_with_program(&program);
+    cpu.keyboard.on_key_down(0x7);
+    run_steps(&mut cpu, 3);
+    assert_eq!((cpu.v[1], cpu.v[2]), (1, 0));
+}
+
+#[test]
+fn tracks_edges_per_frame_and_ignores_repeat() {
+    let mut keyboard = Keyboard::new();
+
+    keyboard.on_key_down(0x3);
+    keyboard.on_key_down(0x3);
+    keyboard.on_key_down(0x0);
+    assert_eq!(keyboard.held, 0b1001);
+    assert_eq!(keyboard.pressed, 0b1001);
+
+    // A single release lets go of a key however often it repeated.
+    keyboard.clear_edges();
+    keyboard.on_key_up(0x3);
+    assert_eq!(keyboard.held, 0b0001);
+    assert_eq!((keyboard.pressed, keyboard.released), (0, 0b1000));
+    assert!(keyboard.is_key_pressed(0x0));
+    assert!(!keyboard.is_key_pressed(0x3));
+
+    keyboard.set(0b0110);
+    assert_eq!((keyboard.pressed, keyboard.released), (0b0110, 0b1001));
+}
+
+#[test]
+fn host_devices_hold_keys_independently() {
+    let mut keys = HostKeys::new();
+
+    keys.set(Source::Keyboard, 0x5, true);
+    keys.set(Source::Gamepad(1), 0x5, true);
+    keys.set(Source::Gamepad(1), 0x2, true);
+    keys.set(Source::Gamepad(1), 0x5, false);
+    assert_eq!(keys.held(), 1 << 0x5 | 1 << 0x2);
+
+    // Unplugging a controller lets go of what it held.
+    keys.remove_gamepad(1);
+    assert_eq!(keys.held(), 1 << 0x5);
+}
+
+#[test]
+fn scripted_input_drives_the_main_loop() {
+    // F00A 00FD: wait for a key, then exit.
+    let path = std::env::temp_dir().join("rust-chip-keypad.ch8");
+    std::fs::write(&path, [0xF0, 0x0A, 0x00, 0xFD]).unwrap();
+
+    let input = Scripted::new(&[0, 0, 1 << 5, 1 << 5, 1 << 5, 1 << 5, 0, 0]);
+    let mut chip8 = Chip8::new(Cpu::new(), &path, Box::new(Headless), Box::new(input), Box::new(Headless)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    chip8.run().unwrap();
+    assert!(chip8.cpu.exited);
+    assert_eq!(chip8.cpu.v[0], 5);
+}
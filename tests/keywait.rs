@@ -0,0 +1,106 @@
+mod common;
+
+use common::cpu_with_program;
+use rust_chip::cpu::{Cpu, KeyWait};
+
+// F30A 1202: wait for a key into V3, then spin.
+const PROGRAM: [u8; 4] = [0xF3, 0x0A, 0x12, 0x02];
+
+/// Runs one instruction per step, pressing (`true`) or releasing keys
+/// before the steps they are listed with.
+fn run_script(cpu: &mut Cpu, script: &[(usize, u8, bool)], steps: usize) {
+    for step in 0..steps {
+        for (_, key, down) in script.iter().filter(|event| event.0 == step) {
+            if *down {
+                cpu.keyboard.on_key_down(*key);
+            } else {
+                cpu.keyboard.on_key_up(*key);
+            }
+        }
+        cpu.step().unwrap();
+    }
+}
+
+#[test]
+fn waits_for_press_and_release() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    cpu.quirks.key_wait_release = true;
+
+    run_script(&mut cpu, &[(2, 0x7, true)], 4);
+    assert_eq!(cpu.pc, 0x200);
+    assert_eq!(cpu.key_wait, KeyWait::Pressed(0x7));
+
+    run_script(&mut cpu, &[(0, 0x7, false)], 1);
+    assert_eq!(cpu.pc, 0x202);
+    assert_eq!(cpu.v[3], 0x7);
+    assert_eq!(cpu.key_wait, KeyWait::Idle);
+}
+
+#[test]
+fn completes_on_press_without_the_release_quirk() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    cpu.quirks.key_wait_release = false;
+
+    run_script(&mut cpu, &[(1, 0x0, true)], 2);
+    assert_eq!(cpu.pc, 0x202);
+    assert_eq!(cpu.v[3], 0x0);
+}
+
+#[test]
+fn ignores_keys_held_before_the_wait() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    cpu.quirks.key_wait_release = false;
+    cpu.keyboard.on_key_down(0xA);
+
+    // Releasing another key or keeping A down does not count as a press.
+    run_script(&mut cpu, &[(1, 0x5, true), (2, 0x5, false)], 3);
+    assert_eq!(cpu.pc, 0x202);
+    assert_eq!(cpu.v[3], 0x5);
+
+    let mut cpu = cpu_with_program(&PROGRAM);
+    cpu.keyboard.on_key_down(0xA);
+    run_script(&mut cpu, &[], 5);
+    assert_eq!(cpu.pc, 0x200);
+
+    // Pressing A again after letting go does.
+    run_script(&mut cpu, &[(0, 0xA, false), (2, 0xA, true)], 3);
+    assert_eq!(cpu.pc, 0x202);
+    assert_eq!(cpu.v[3], 0xA);
+}
+
+#[test]
+fn timers_keep_running_while_waiting() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    cpu.delay_timer = 10;
+    cpu.sound_timer = 5;
+
+    for _ in 0..4 {
+        cpu.cycle().unwrap();
+    }
+
+    assert_eq!(cpu.pc, 0x200);
+    assert_eq!((cpu.delay_timer, cpu.sound_timer), (6, 1));
+}
+
+#[test]
+fn registers_a_tap_between_polls() {
+    for release in [false, true].iter() {
+        let mut cpu = cpu_with_program(&PROGRAM);
+        cpu.quirks.key_wait_release = *release;
+
+        // The key goes down and up again before FX0A runs a second time.
+        run_script(&mut cpu, &[(1, 0x4, true), (1, 0x4, false)], 2);
+        assert_eq!(cpu.pc, 0x202);
+        assert_eq!(cpu.v[3], 0x4);
+    }
+}
+
+#[test]
+fn reads_a_tap_only_once() {
+    // F30A F40A 1204: wait for two keys, then spin.
+    let mut cpu = cpu_with_program(&[0xF3, 0x0A, 0xF4, 0x0A, 0x12, 0x04]);
+
+    run_script(&mut cpu, &[(1, 0x4, true), (1, 0x4, false)], 5);
+    assert_eq!(cpu.pc, 0x202);
+    assert_eq!(cpu.v[3], 0x4);
+}
@@ -0,0 +1,183 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::chip8::Chip8;
+use rust_chip::cpu::Cpu;
+use rust_chip::frontend::{Command, Headless, Input};
+use rust_chip::keyboard::Keyboard;
+use rust_chip::movie::{Frame, Movie};
+use rust_chip::quirks::Quirks;
+use rust_chip::rng::Rng;
+use rust_chip::savestate;
+use std::path::{Path, PathBuf};
+
+fn chip8(rom: &str) -> Chip8 {
+    return Chip8::new(Cpu::new(), Path::new(rom), Box::new(Headless), Box::new(Headless), Box::new(Headless)).unwrap();
+}
+
+/// Writes `F30A 1202`, wait for a key into V3 and spin, to a temporary ROM.
+fn key_wait_rom(name: &str) -> PathBuf {
+    let path = std::env::temp_dir().join(name);
+    std::fs::write(&path, [0xF3, 0x0A, 0x12, 0x02]).unwrap();
+
+    return path;
+}
+
+/// Taps key 5 on every poll and quits after a few.
+struct Tapping {
+    polls: u32,
+}
+
+impl Input for Tapping {
+    fn poll(&mut self, keyboard: &mut Keyboard) -> Vec<Command> {
+        self.polls += 1;
+        keyboard.on_key_down(0x5);
+        keyboard.on_key_up(0x5);
+
+        return match self.polls {
+            6 => vec![Command::Quit],
+            _ => Vec::new(),
+        };
+    }
+}
+
+#[test]
+fn seeded_random_numbers_repeat() {
+    // C0FF C1FF: two random bytes.
+    let program = [0xC0, 0xFF, 0xC1, 0xFF];
+    let mut first = cpu_with_program(&program);
+    let mut second = cpu_with_program(&program);
+    first.rng = Rng::new(42);
+    second.rng = Rng::new(42);
+
+    run_steps(&mut first, 2);
+    run_steps(&mut second, 2);
+    assert_eq!(first.v[..2], second.v[..2]);
+    assert!(first.v[0] != first.v[1]);
+}
+
+#[test]
+fn replays_a_recorded_run_bit_for_bit() {
+    let mut recorder = chip8("roms/BRIX.ch8");
+    recorder.cpu.rng = Rng::new(7);
+    recorder.record_movie();
+
+    for frame in 0..600 {
+        // Move the paddle right for a while, then left.
+        match frame {
+            120 => recorder.cpu.keyboard.on_key_down(0x6),
+            300 => recorder.cpu.keyboard.on_key_up(0x6),
+            360 => recorder.cpu.keyboard.on_key_down(0x4),
+            _ => {}
+        }
+        recorder.tick().unwrap();
+    }
+    let movie = Movie::from_bytes(&recorder.recording.as_ref().unwrap().to_bytes()).unwrap();
+    assert_eq!(movie.frames.len(), 600);
+    assert_eq!(
+        movie.frames[200],
+        Frame {
+            keys: 1 << 0x6,
+            pressed: 0,
+            released: 0
+        }
+    );
+    assert_eq!(movie.frames[120].pressed, 1 << 0x6);
+
+    let mut player = chip8("roms/BRIX.ch8");
+    player.play_movie(movie).unwrap();
+    for _ in 0..600 {
+        player.tick().unwrap();
+    }
+
+    assert!(savestate::save(&player.cpu) == savestate::save(&recorder.cpu));
+}
+
+#[test]
+fn refuses_movies_of_other_roms() {
+    let mut recorder = chip8("roms/BRIX.ch8");
+    recorder.record_movie();
+    recorder.tick().unwrap();
+    let movie = recorder.recording.unwrap();
+
+    let mut player = chip8("roms/BLITZ.ch8");
+    assert!(player.play_movie(movie.clone()).is_err());
+
+    let mut data = movie.to_bytes();
+    data.pop();
+    assert!(Movie::from_bytes(&data).is_err());
+    assert!(Movie::from_bytes(b"CHIP8SAV").is_err());
+}
+
+#[test]
+fn playback_loads_the_rom_with_the_recorded_quirks() {
+    let mut recorder = chip8("roms/BRIX.ch8");
+    recorder.cpu.quirks = Quirks::xo_chip();
+    recorder.record_movie();
+    let movie = recorder.recording.unwrap();
+
+    let mut player = chip8("roms/BRIX.ch8");
+    assert_eq!(player.cpu.memory.len(), 0x1000);
+    player.play_movie(movie).unwrap();
+
+    assert_eq!(player.cpu.quirks, Quirks::xo_chip());
+    assert_eq!(player.cpu.memory.len(), 0x10000);
+    let rom = std::fs::read("roms/BRIX.ch8").unwrap();
+    assert_eq!(&player.cpu.memory[0x200..0x200 + rom.len()], &rom[..]);
+}
+
+#[test]
+fn replays_taps_between_polls() {
+    let path = key_wait_rom("rust-chip-movie-tap.ch8");
+    let mut recorder = chip8(path.to_str().unwrap());
+    recorder.record_movie();
+    recorder.tick().unwrap();
+    recorder.cpu.keyboard.on_key_down(0x5);
+    recorder.cpu.keyboard.on_key_up(0x5);
+    recorder.tick().unwrap();
+    assert_eq!((recorder.cpu.pc, recorder.cpu.v[3]), (0x202, 0x5));
+
+    let movie = Movie::from_bytes(&recorder.recording.unwrap().to_bytes()).unwrap();
+    let mut player = chip8(path.to_str().unwrap());
+    std::fs::remove_file(&path).unwrap();
+    player.play_movie(movie).unwrap();
+    player.tick().unwrap();
+    player.tick().unwrap();
+
+    assert_eq!((player.cpu.pc, player.cpu.v[3]), (0x202, 0x5));
+}
+
+#[test]
+fn playback_ignores_host_input() {
+    let path = key_wait_rom("rust-chip-movie-host.ch8");
+    let mut recorder = chip8(path.to_str().unwrap());
+    recorder.record_movie();
+    let mut movie = recorder.recording.unwrap();
+    movie.frames = vec![Frame::from_keyboard(&Keyboard::new()); 600];
+
+    let input = Tapping { polls: 0 };
+    let mut player = Chip8::new(Cpu::new(), &path, Box::new(Headless), Box::new(input), Box::new(Headless)).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    player.play_movie(movie).unwrap();
+    player.run().unwrap();
+
+    assert_eq!((player.cpu.pc, player.cpu.v[3]), (0x200, 0));
+}
+
+#[test]
+fn playback_uses_the_recorded_stack_depth_and_load_address() {
+    let mut cpu = Cpu::new();
+    cpu.set_stack_depth(4);
+    cpu.load_address = 0x300;
+    let mut recorder = Chip8::new(cpu, Path::new("roms/BRIX.ch8"), Box::new(Headless), Box::new(Headless), Box::new(Headless)).unwrap();
+    recorder.record_movie();
+    let movie = Movie::from_bytes(&recorder.recording.unwrap().to_bytes()).unwrap();
+
+    let mut player = chip8("roms/BRIX.ch8");
+    player.play_movie(movie).unwrap();
+
+    assert_eq!(player.cpu.stack.len(), 4);
+    assert_eq!(player.cpu.pc, 0x300);
+    let rom = std::fs::read("roms/BRIX.ch8").unwrap();
+    assert_eq!(&player.cpu.memory[0x300..0x300 + rom.len()], &rom[..]);
+}
@@ -0,0 +1,121 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::config::Config;
+use rust_chip::quirks::Quirks;
+
+#[test]
+fn shift_in_place_or_from_vy() {
+    // LD V1, 0x03 / LD V2, 0x80 / SHR V1, V2
+    let program = [0x61, 0x03, 0x62, 0x80, 0x81, 0x26];
+
+    let mut cpu = cpu_with_program(&program);
+    run_steps(&mut cpu, 3);
+    assert_eq!(cpu.v[1], 0x01);
+    assert_eq!(cpu.v[0xF], 1);
+
+    let mut cpu = cpu_with_program(&program);
+    cpu.quirks.shift_uses_vy = true;
+    run_steps(&mut cpu, 3);
+    assert_eq!(cpu.v[1], 0x40);
+    assert_eq!(cpu.v[0xF], 0);
+}
+
+#[test]
+fn load_store_increments_i() {
+    // LD I, 0x300 / LD [I], V2
+    let program = [0xA3, 0x00, 0xF2, 0x55];
+
+    let mut cpu = cpu_with_program(&program);
+    run_steps(&mut cpu, 2);
+    assert_eq!(cpu.i, 0x300);
+
+    let mut cpu = cpu_with_program(&program);
+    cpu.quirks.load_store_increments_i = true;
+    run_steps(&mut cpu, 2);
+    assert_eq!(cpu.i, 0x303);
+}
+
+#[test]
+fn jump_with_offset_uses_v0_or_vx() {
+    // LD V0, 0x02 / LD V3, 0x10 / JP V0, 0x300
+    let program = [0x60, 0x02, 0x63, 0x10, 0xB3, 0x00];
+
+    let mut cpu = cpu_with_program(&program);
+    run_steps(&mut cpu, 3);
+    assert_eq!(cpu.pc, 0x302);
+
+    let mut cpu = cpu_with_program(&program);
+    cpu.quirks.jump_uses_vx = true;
+    run_steps(&mut cpu, 3);
+    assert_eq!(cpu.pc, 0x310);
+}
+
+#[test]
+fn logic_ops_reset_vf() {
+    // LD VF, 0x05 / OR V1, V2
+    let program = [0x6F, 0x05, 0x81, 0x21];
+
+    let mut cpu = cpu_with_program(&program);
+    run_steps(&mut cpu, 2);
+    assert_eq!(cpu.v[0xF], 5);
+
+    let mut cpu = cpu_with_program(&program);
+    cpu.quirks.vf_reset = true;
+    run_steps(&mut cpu, 2);
+    assert_eq!(cpu.v[0xF], 0);
+}
+
+#[test]
+fn sprites_wrap_or_clip_at_the_edge() {
+    // LD V0, 0x3E / LD V1, 0x00 / LD I, 0x0 / DRW V0, V1, 1
+    let program = [0x60, 0x3E, 0x61, 0x00, 0xA0, 0x00, 0xD0, 0x11];
+
+    let mut cpu = cpu_with_program(&program);
+    run_steps(&mut cpu, 4);
+    assert!(cpu.framebuffer.get_pixel(63, 0));
+    assert!(cpu.framebuffer.get_pixel(0, 0));
+    assert!(cpu.framebuffer.get_pixel(1, 0));
+
+    let mut cpu = cpu_with_program(&program);
+    cpu.quirks.clip_sprites = true;
+    run_steps(&mut cpu, 4);
+    assert!(cpu.framebuffer.get_pixel(63, 0));
+    assert!(!cpu.framebuffer.get_pixel(0, 0));
+}
+
+#[test]
+fn display_wait_allows_one_draw_per_frame() {
+    // DRW V0, V0, 1 / DRW V0, V0, 1
+    let mut cpu = cpu_with_program(&[0xD0, 0x01, 0xD0, 0x01]);
+    cpu.quirks.display_wait = true;
+
+    run_steps(&mut cpu, 5);
+    assert_eq!(cpu.pc, 0x202);
+
+    cpu.update_timers();
+    cpu.step().unwrap();
+    assert_eq!(cpu.pc, 0x204);
+}
+
+#[test]
+fn profiles_from_name_and_config() {
+    assert_eq!(Quirks::from_name("vip").unwrap(), Quirks::cosmac_vip());
+    assert!(Quirks::from_name("nope").is_err());
+
+    let config = Config::parse("[quirks]\nprofile = schip\nclip_sprites = false\n").unwrap();
+    let quirks = Quirks::from_config(&config).unwrap();
+
+    assert!(quirks.jump_uses_vx);
+    assert!(!quirks.clip_sprites);
+    assert_eq!(Quirks::from_config(&Config::new()).unwrap(), Quirks::modern());
+}
+
+#[test]
+fn rom_config_overrides_global_config() {
+    let mut config = Config::parse("[quirks]\nprofile = vip\nvf_reset = false\n").unwrap();
+    config.merge(Config::parse("[quirks]\nprofile = chip48\n").unwrap());
+
+    assert_eq!(config.get("quirks", "profile"), Some("chip48"));
+    assert_eq!(config.get("quirks", "vf_reset"), Some("false"));
+}
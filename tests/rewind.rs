@@ -0,0 +1,76 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::debugger::{DebugCommand, Debugger};
+use rust_chip::rewind::Rewind;
+use rust_chip::savestate;
+
+// 7001 A300 F055 1200: count up in V0 and store it at 0x300.
+const PROGRAM: [u8; 8] = [0x70, 0x01, 0xA3, 0x00, 0xF0, 0x55, 0x12, 0x00];
+
+#[test]
+fn steps_back_through_recorded_states() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let mut rewind = Rewind::new(1 << 20);
+    let mut states = Vec::new();
+
+    for frame in 0..5 {
+        let state = savestate::save(&cpu);
+        states.push(state.clone());
+        rewind.record(state, frame);
+        run_steps(&mut cpu, 4);
+    }
+    assert_eq!(rewind.len(), 4);
+
+    for frame in (0..4).rev() {
+        let (state, instructions) = rewind.back().unwrap();
+        assert_eq!(state, states[frame]);
+        assert_eq!(instructions, frame as u32 + 1);
+    }
+    assert!(rewind.back().is_none());
+    assert!(rewind.is_empty());
+
+    savestate::load(&mut cpu, &states[0]).unwrap();
+    assert_eq!(cpu.v[0], 0);
+    assert_eq!(cpu.pc, 0x200);
+}
+
+#[test]
+fn frames_are_stored_as_small_deltas() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let mut rewind = Rewind::new(1 << 20);
+    let size = savestate::save(&cpu).len();
+
+    for _ in 0..100 {
+        rewind.record(savestate::save(&cpu), 4);
+        run_steps(&mut cpu, 4);
+    }
+    assert_eq!(rewind.len(), 99);
+    assert!(rewind.memory_used() < size * 2);
+}
+
+#[test]
+fn drops_the_oldest_states_over_budget() {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    let size = savestate::save(&cpu).len();
+    let mut rewind = Rewind::new(size + 200);
+
+    for _ in 0..100 {
+        rewind.record(savestate::save(&cpu), 4);
+        run_steps(&mut cpu, 4);
+    }
+    assert!(rewind.memory_used() <= size + 200);
+    assert!(!rewind.is_empty() && rewind.len() < 99);
+
+    let mut disabled = Rewind::new(0);
+    disabled.record(savestate::save(&cpu), 0);
+    disabled.record(savestate::save(&cpu), 1);
+    assert!(disabled.back().is_none());
+}
+
+#[test]
+fn parses_backward_steps() {
+    assert_eq!(Debugger::parse("bs"), Ok(DebugCommand::StepBack(1)));
+    assert_eq!(Debugger::parse("back 3"), Ok(DebugCommand::StepBack(3)));
+    assert_eq!(Debugger::parse("bf"), Ok(DebugCommand::FrameBack));
+}
@@ -0,0 +1,138 @@
+use flate2::write::{DeflateEncoder, GzEncoder};
+use flate2::Compression;
+use rust_chip::cpu::{Cpu, MEMORY_SIZE};
+use rust_chip::error::Chip8Error;
+use rust_chip::rom::{self, ETI_660_START};
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+
+/// A zip archive with the given files, deflating the ones marked so.
+fn zip(files: &[(&str, &[u8], bool)]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut directory = Vec::new();
+
+    for (name, data, deflate) in files.iter() {
+        let stored = if *deflate {
+            let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+            encoder.write_all(data).unwrap();
+            encoder.finish().unwrap()
+        } else {
+            data.to_vec()
+        };
+        let method: u16 = if *deflate { 8 } else { 0 };
+        let offset = out.len() as u32;
+
+        // Sizes left out of the local header, as streaming zippers do.
+        out.extend_from_slice(b"PK\x03\x04\x14\x00\x08\x00");
+        out.extend_from_slice(&method.to_le_bytes());
+        out.extend_from_slice(&[0; 16]);
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&[0, 0]);
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(&stored);
+
+        directory.extend_from_slice(b"PK\x01\x02\x14\x00\x14\x00\x08\x00");
+        directory.extend_from_slice(&method.to_le_bytes());
+        directory.extend_from_slice(&[0; 8]);
+        directory.extend_from_slice(&(stored.len() as u32).to_le_bytes());
+        directory.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        directory.extend_from_slice(&[0; 12]);
+        directory.extend_from_slice(&offset.to_le_bytes());
+        directory.extend_from_slice(name.as_bytes());
+    }
+
+    let start = out.len() as u32;
+    out.extend_from_slice(&directory);
+    out.extend_from_slice(b"PK\x05\x06\0\0\0\0");
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    out.extend_from_slice(&(directory.len() as u32).to_le_bytes());
+    out.extend_from_slice(&start.to_le_bytes());
+    out.extend_from_slice(&[0, 0]);
+
+    return out;
+}
+
+#[test]
+fn writes_the_rom_in_place() {
+    let mut cpu = Cpu::new();
+    cpu.load_program(Path::new("roms/BC_test.ch8")).unwrap();
+    let rom = fs::read("roms/BC_test.ch8").unwrap();
+
+    assert_eq!(cpu.memory.len(), MEMORY_SIZE);
+    assert_eq!(&cpu.memory[0x200..0x200 + rom.len()], &rom[..]);
+    assert!(cpu.memory[0x200 + rom.len()..].iter().all(|byte| *byte == 0));
+    assert_eq!(cpu.pc, 0x200);
+}
+
+#[test]
+fn loads_eti_660_programs_at_0x600() {
+    let mut cpu = Cpu::new();
+    cpu.load_address = ETI_660_START;
+    cpu.load_rom(&[0x60, 0x01]).unwrap();
+
+    assert_eq!(cpu.pc, 0x600);
+    assert_eq!(&cpu.memory[0x600..0x602], &[0x60, 0x01]);
+    assert_eq!(cpu.memory[0x200], 0);
+    assert_eq!(
+        cpu.load_rom(&[0; 0xA01]),
+        Err(Chip8Error::RomTooLarge {
+            size: 0xA01,
+            available: 0xA00
+        })
+    );
+}
+
+#[test]
+fn roms_loaded_over_the_fonts_replace_them() {
+    let mut cpu = Cpu::new();
+    cpu.load_address = 0;
+    cpu.load_rom(&[0x12, 0x34]).unwrap();
+
+    assert_eq!(&cpu.memory[0..2], &[0x12, 0x34]);
+    assert_eq!(cpu.pc, 0);
+}
+
+#[test]
+fn rejects_load_addresses_past_the_end_of_memory() {
+    let mut cpu = Cpu::new();
+    cpu.load_address = 0x2000;
+
+    assert_eq!(
+        cpu.load_rom(&[]),
+        Err(Chip8Error::MemoryOutOfBounds {
+            address: 0x2000,
+            target: 0x2000
+        })
+    );
+}
+
+#[test]
+fn unpacks_gzip_files() {
+    let rom = fs::read("roms/BRIX.ch8").unwrap();
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(&rom).unwrap();
+    let path = std::env::temp_dir().join("rust-chip-brix.ch8.gz");
+    fs::write(&path, encoder.finish().unwrap()).unwrap();
+
+    let result = rom::read(&path);
+    fs::remove_file(&path).unwrap();
+
+    assert_eq!(result, Ok(rom));
+}
+
+#[test]
+fn unpacks_the_program_of_zip_archives() {
+    let rom = fs::read("roms/BLITZ.ch8").unwrap();
+
+    let archive = zip(&[("README.txt", b"Blitz", false), ("games/BLITZ.ch8", &rom, true)]);
+    assert_eq!(rom::unpack(archive), Ok(rom.clone()));
+
+    let stored = zip(&[("BLITZ.CH8", &rom, false)]);
+    assert_eq!(rom::unpack(stored), Ok(rom.clone()));
+
+    assert!(rom::unpack(zip(&[("a.ch8", &rom, false), ("b.ch8", &rom, true)])).is_err());
+    assert!(rom::unpack(zip(&[("README.txt", b"Blitz", false)])).is_err());
+}
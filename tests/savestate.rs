@@ -0,0 +1,88 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::quirks::Quirks;
+use rust_chip::savestate::{self, rom_hash, VERSION};
+
+// 6005 A20A D015 7001 1204, then a sprite byte.
+const PROGRAM: [u8; 11] = [0x60, 0x05, 0xA2, 0x0A, 0xD0, 0x15, 0x70, 0x01, 0x12, 0x04, 0xF0];
+
+fn cpu() -> rust_chip::cpu::Cpu {
+    let mut cpu = cpu_with_program(&PROGRAM);
+    cpu.rom_hash = rom_hash(&PROGRAM);
+
+    return cpu;
+}
+
+#[test]
+fn restores_the_saved_machine() {
+    let mut cpu = cpu();
+    cpu.quirks = Quirks::chip48();
+    run_steps(&mut cpu, 3);
+    cpu.sound_timer = 7;
+    let state = savestate::save(&cpu);
+
+    let pixels = cpu.framebuffer.pixels.clone();
+    run_steps(&mut cpu, 10);
+    cpu.quirks = Quirks::modern();
+    cpu.sound_timer = 0;
+    cpu.memory[0x300] = 0xAA;
+    assert!(cpu.framebuffer.pixels != pixels);
+
+    savestate::load(&mut cpu, &state).unwrap();
+    assert_eq!(cpu.pc, 0x206);
+    assert_eq!(cpu.v[0], 5);
+    assert_eq!(cpu.i, 0x20A);
+    assert_eq!(cpu.sound_timer, 7);
+    assert_eq!(cpu.memory[0x300], 0);
+    assert_eq!(cpu.quirks, Quirks::chip48());
+    assert_eq!(cpu.framebuffer.pixels, pixels);
+    assert_eq!(savestate::save(&cpu), state);
+}
+
+#[test]
+fn restores_hires_and_stack() {
+    // 00FF 2206 1204 00EE
+    let program = [0x00, 0xFF, 0x22, 0x06, 0x12, 0x04, 0x00, 0xEE];
+    let mut cpu = cpu_with_program(&program);
+    run_steps(&mut cpu, 2);
+    let state = savestate::save(&cpu);
+
+    let mut other = cpu_with_program(&program);
+    savestate::load(&mut other, &state).unwrap();
+    assert!(other.framebuffer.hires);
+    assert_eq!(other.framebuffer.pixels.len(), 128 * 64);
+    assert_eq!(other.stack, cpu.stack);
+    assert_eq!(other.pc, 0x206);
+}
+
+#[test]
+fn rejects_states_of_other_roms() {
+    let mut cpu = cpu();
+    let state = savestate::save(&cpu);
+
+    cpu.rom_hash = rom_hash(&[0x12, 0x00]);
+    run_steps(&mut cpu, 1);
+    assert_eq!(
+        savestate::load(&mut cpu, &state).unwrap_err(),
+        "The save state belongs to a different ROM"
+    );
+    assert_eq!(cpu.pc, 0x202);
+}
+
+#[test]
+fn rejects_damaged_files() {
+    let mut cpu = cpu();
+    let state = savestate::save(&cpu);
+
+    assert!(savestate::load(&mut cpu, b"hello").is_err());
+    assert!(savestate::load(&mut cpu, &state[..state.len() - 1]).is_err());
+
+    let mut newer = state.clone();
+    newer[8..10].copy_from_slice(&(VERSION + 1).to_le_bytes());
+    assert!(savestate::load(&mut cpu, &newer).unwrap_err().contains("version"));
+
+    let mut longer = state;
+    longer.push(0);
+    assert!(savestate::load(&mut cpu, &longer).is_err());
+}
@@ -0,0 +1,46 @@
+use rust_chip::scheduler::{tick_period, ClockRate, Scheduler, MAX_CATCH_UP_TICKS, TIMER_HZ};
+use std::time::{Duration, Instant};
+
+#[test]
+fn ticks_at_sixty_hertz() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(ClockRate::PerFrame(10));
+    scheduler.start(start);
+
+    assert_eq!(scheduler.due_ticks(start), 1);
+    assert_eq!(scheduler.due_ticks(start + Duration::from_millis(10)), 0);
+    assert_eq!(scheduler.due_ticks(start + tick_period()), 1);
+
+    // One second later the remaining ticks of that second are due, capped.
+    let mut total = 2;
+    let mut now = start + tick_period();
+    while now < start + Duration::from_secs(1) {
+        now += Duration::from_millis(5);
+        total += scheduler.due_ticks(now);
+    }
+    assert_eq!(total, TIMER_HZ + 1);
+    assert_eq!(scheduler.skipped_ticks, 0);
+}
+
+#[test]
+fn drops_ticks_when_far_behind() {
+    let start = Instant::now();
+    let mut scheduler = Scheduler::new(ClockRate::PerFrame(10));
+    scheduler.start(start);
+
+    assert_eq!(scheduler.due_ticks(start + Duration::from_secs(1)), MAX_CATCH_UP_TICKS);
+    assert_eq!(scheduler.skipped_ticks, (TIMER_HZ + 1 - MAX_CATCH_UP_TICKS) as u64);
+
+    // The clock resynchronises instead of trying to catch up.
+    assert_eq!(scheduler.due_ticks(start + Duration::from_secs(1)), 0);
+}
+
+#[test]
+fn spreads_hertz_rate_across_ticks() {
+    let mut scheduler = Scheduler::new(ClockRate::Hertz(700));
+
+    let counts: Vec<u32> = (0..TIMER_HZ).map(|_| scheduler.instructions_for_tick()).collect();
+
+    assert_eq!(counts.iter().sum::<u32>(), 700);
+    assert!(counts.iter().all(|&count| count == 11 || count == 12));
+}
@@ -0,0 +1,88 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::cpu::BIG_FONT_ADDRESS;
+
+#[test]
+fn switches_between_low_and_high_resolution() {
+    // HIGH / LOW
+    let mut cpu = cpu_with_program(&[0x00, 0xFF, 0x00, 0xFE]);
+
+    cpu.step().unwrap();
+    assert!(cpu.framebuffer.hires);
+    assert_eq!((cpu.framebuffer.width, cpu.framebuffer.height), (128, 64));
+    assert_eq!(cpu.framebuffer.pixels.len(), 128 * 64);
+
+    cpu.step().unwrap();
+    assert_eq!((cpu.framebuffer.width, cpu.framebuffer.height), (64, 32));
+}
+
+#[test]
+fn draws_sixteen_by_sixteen_sprites() {
+    // HIGH / LD I, 0x300 / DRW V0, V0, 0
+    let mut cpu = cpu_with_program(&[0x00, 0xFF, 0xA3, 0x00, 0xD0, 0x00]);
+    for row in 0..16 {
+        cpu.memory[0x300 + row * 2] = 0x80;
+        cpu.memory[0x300 + row * 2 + 1] = 0x01;
+    }
+
+    run_steps(&mut cpu, 3);
+
+    assert!(cpu.framebuffer.get_pixel(0, 15));
+    assert!(cpu.framebuffer.get_pixel(15, 15));
+    assert!(!cpu.framebuffer.get_pixel(1, 0));
+    assert!(!cpu.framebuffer.get_pixel(0, 16));
+}
+
+#[test]
+fn scrolls_the_screen() {
+    // LD I, 0x0 / DRW V0, V0, 1 / SCD 2 / SCR / SCL / SCL
+    let program = [0xA0, 0x00, 0xD0, 0x01, 0x00, 0xC2, 0x00, 0xFB, 0x00, 0xFC, 0x00, 0xFC];
+    let mut cpu = cpu_with_program(&program);
+
+    run_steps(&mut cpu, 3);
+    assert!(cpu.framebuffer.get_pixel(0, 2));
+    assert!(!cpu.framebuffer.get_pixel(0, 0));
+
+    cpu.step().unwrap();
+    assert!(!cpu.framebuffer.get_pixel(0, 2));
+    assert!(cpu.framebuffer.get_pixel(4, 2));
+
+    cpu.step().unwrap();
+    assert!(cpu.framebuffer.get_pixel(3, 2));
+    assert!(!cpu.framebuffer.get_pixel(4, 2));
+
+    // Pixels scrolled off the edge are gone.
+    cpu.step().unwrap();
+    assert!(cpu.framebuffer.pixels.iter().all(|&pixel| pixel == 0));
+}
+
+#[test]
+fn points_i_at_big_font_digits() {
+    // LD V2, 0x7 / LD HF, V2
+    let mut cpu = cpu_with_program(&[0x62, 0x07, 0xF2, 0x30]);
+
+    run_steps(&mut cpu, 2);
+
+    assert_eq!(cpu.i as usize, BIG_FONT_ADDRESS + 70);
+}
+
+#[test]
+fn saves_and_restores_rpl_flags() {
+    // LD V0, 0x11 / LD V1, 0x22 / LD R, V1 / LD V0, 0x0 / LD V1, 0x0 / LD V1, R
+    let mut cpu = cpu_with_program(&[0x60, 0x11, 0x61, 0x22, 0xF1, 0x75, 0x60, 0x00, 0x61, 0x00, 0xF1, 0x85]);
+
+    run_steps(&mut cpu, 6);
+
+    assert_eq!(&cpu.v[..2], &[0x11, 0x22]);
+    assert_eq!(&cpu.rpl[..2], &[0x11, 0x22]);
+}
+
+#[test]
+fn exit_stops_the_interpreter() {
+    let mut cpu = cpu_with_program(&[0x00, 0xFD]);
+
+    cpu.step().unwrap();
+
+    assert!(cpu.exited);
+}
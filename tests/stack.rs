@@ -0,0 +1,50 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::error::Chip8Error;
+
+#[test]
+fn returns_after_the_call() {
+    // 2206 6001 1204 / sub: 6102 00EE
+    let mut cpu = cpu_with_program(&[0x22, 0x06, 0x60, 0x01, 0x12, 0x04, 0x61, 0x02, 0x00, 0xEE]);
+
+    run_steps(&mut cpu, 1);
+    assert_eq!(cpu.pc, 0x206);
+    assert_eq!(cpu.call_stack(), &[0x202]);
+
+    run_steps(&mut cpu, 3);
+    assert_eq!(cpu.pc, 0x204);
+    assert_eq!((cpu.v[0], cpu.v[1]), (1, 2));
+    assert!(cpu.call_stack().is_empty());
+}
+
+#[test]
+fn unwinds_nested_calls_in_order() {
+    // 2208 6001 1204 0000 / a: 220E 7010 00EE / b: 7002 00EE
+    let program = [
+        0x22, 0x08, 0x60, 0x01, 0x12, 0x04, 0x00, 0x00, 0x22, 0x0E, 0x70, 0x10, 0x00, 0xEE, 0x70, 0x02, 0x00,
+        0xEE,
+    ];
+    let mut cpu = cpu_with_program(&program);
+
+    run_steps(&mut cpu, 2);
+    assert_eq!(cpu.call_stack(), &[0x202, 0x20A]);
+    assert_eq!(cpu.sp, 2);
+
+    run_steps(&mut cpu, 5);
+    assert_eq!(cpu.pc, 0x204);
+    assert_eq!(cpu.v[0], 1);
+    assert_eq!(cpu.sp, 0);
+}
+
+#[test]
+fn recursion_is_bounded_by_the_configured_depth() {
+    // 2200: call itself forever.
+    let mut cpu = cpu_with_program(&[0x22, 0x00]);
+    cpu.set_stack_depth(12);
+
+    run_steps(&mut cpu, 12);
+    assert_eq!(cpu.call_stack(), &[0x202; 12]);
+    assert_eq!(cpu.step(), Err(Chip8Error::StackOverflow { address: 0x200 }));
+    assert_eq!(cpu.sp, 12);
+}
@@ -0,0 +1,70 @@
+use rust_chip::framebuffer::Framebuffer;
+use rust_chip::image;
+use rust_chip::palette::Palette;
+use rust_chip::terminal::{braille, half_blocks, parse_keys};
+
+fn key(name: &str, shift: bool) -> (String, bool) {
+    return (String::from(name), shift);
+}
+
+/// The characters of the text without its color escapes.
+fn strip_colors(text: &str) -> String {
+    let mut out = String::new();
+    let mut escape = false;
+
+    for c in text.chars() {
+        match c {
+            '\x1b' => escape = true,
+            'm' if escape => escape = false,
+            _ if !escape => out.push(c),
+            _ => {}
+        }
+    }
+
+    return out;
+}
+
+#[test]
+fn parses_keys_and_escape_sequences() {
+    assert_eq!(parse_keys(b"qW1"), vec![key("Q", false), key("W", true), key("1", false)]);
+    assert_eq!(parse_keys(b"\x1b"), vec![key("Escape", false)]);
+    assert_eq!(parse_keys(b"\x1b[A\x1b[1;2D"), vec![key("Up", false), key("Left", true)]);
+    assert_eq!(parse_keys(b"\x1bOP\x1b[15~\x1b[24;2~"), vec![key("F1", false), key("F5", false), key("F12", true)]);
+    assert_eq!(parse_keys(b"\x7f \x03"), vec![key("Backspace", false), key("Space", false), key("Ctrl+C", false)]);
+    // Unknown sequences are skipped whole.
+    assert_eq!(parse_keys(b"\x1b[99~x"), vec![key("X", false)]);
+}
+
+#[test]
+fn draws_two_pixels_per_half_block() {
+    let mut framebuffer = Framebuffer::new();
+    framebuffer.set_pixel(0, 0, 1);
+    framebuffer.set_pixel(1, 0, 1);
+    framebuffer.set_pixel(1, 1, 1);
+
+    let text = half_blocks(&image::rgb(&framebuffer, &Palette::new(), 1), 64);
+    let lines: Vec<String> = text.lines().map(strip_colors).collect();
+
+    assert_eq!(lines.len(), 16);
+    assert!(lines[0].starts_with("▀   "));
+    assert_eq!(lines[0].chars().count(), 64);
+    assert!(text.starts_with("\x1b[38;2;255;255;255;48;2;0;0;0m▀\x1b[38;2;255;255;255;48;2;255;255;255m "));
+}
+
+#[test]
+fn draws_eight_pixels_per_braille_pattern() {
+    let mut framebuffer = Framebuffer::new();
+    framebuffer.set_hires(true);
+    framebuffer.set_pixel(0, 0, 1);
+    framebuffer.set_pixel(1, 3, 1);
+    framebuffer.set_pixel(127, 63, 1);
+
+    let palette = Palette::new();
+    let text = braille(&image::rgb(&framebuffer, &palette, 1), 128, palette.color(0), palette.color(1));
+    let lines: Vec<String> = text.lines().map(strip_colors).collect();
+
+    assert_eq!(lines.len(), 16);
+    assert_eq!(lines[0].chars().count(), 64);
+    assert_eq!(lines[0].chars().next(), Some('\u{2881}'));
+    assert_eq!(lines[15].chars().last(), Some('\u{2880}'));
+}
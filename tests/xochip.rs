@@ -0,0 +1,136 @@
+mod common;
+
+use common::{cpu_with_program, run_steps};
+use rust_chip::config::Config;
+use rust_chip::palette::Palette;
+use rust_chip::speaker::SquareWave;
+
+#[test]
+fn loads_long_addresses_into_i() {
+    // LD I, 0x1234 (long) / LD V0, 0x01
+    let mut cpu = cpu_with_program(&[0xF0, 0x00, 0x12, 0x34, 0x60, 0x01]);
+
+    run_steps(&mut cpu, 2);
+
+    assert_eq!(cpu.i, 0x1234);
+    assert_eq!(cpu.v[0], 0x01);
+}
+
+#[test]
+fn skips_over_long_loads() {
+    // SE V0, 0x00 / LD I, 0x1234 (long) / LD V1, 0x01
+    let mut cpu = cpu_with_program(&[0x30, 0x00, 0xF0, 0x00, 0x12, 0x34, 0x61, 0x01]);
+
+    run_steps(&mut cpu, 2);
+
+    assert_eq!(cpu.i, 0);
+    assert_eq!(cpu.v[1], 0x01);
+}
+
+#[test]
+fn saves_and_loads_register_ranges() {
+    // LD V2, 0x22 / LD V3, 0x33 / LD I, 0x300 / SAVE V2 - V3 / LOAD V5 - V4
+    let mut cpu = cpu_with_program(&[0x62, 0x22, 0x63, 0x33, 0xA3, 0x00, 0x52, 0x32, 0x55, 0x43]);
+
+    run_steps(&mut cpu, 5);
+
+    assert_eq!(&cpu.memory[0x300..0x302], &[0x22, 0x33]);
+    assert_eq!(cpu.v[5], 0x22);
+    assert_eq!(cpu.v[4], 0x33);
+    assert_eq!(cpu.i, 0x300);
+}
+
+#[test]
+fn draws_on_selected_planes() {
+    // PLANE 3 / LD I, 0x300 / DRW V0, V0, 1
+    let mut cpu = cpu_with_program(&[0xF3, 0x01, 0xA3, 0x00, 0xD0, 0x01]);
+    cpu.memory[0x300] = 0xC0; // plane 1
+    cpu.memory[0x301] = 0x80; // plane 2
+
+    run_steps(&mut cpu, 3);
+
+    assert_eq!(cpu.framebuffer.get_color(0, 0), 3);
+    assert_eq!(cpu.framebuffer.get_color(1, 0), 1);
+
+    // CLS only clears the selected plane.
+    cpu.framebuffer.planes = 1;
+    cpu.framebuffer.clear();
+    assert_eq!(cpu.framebuffer.get_color(0, 0), 2);
+    assert_eq!(cpu.framebuffer.get_color(1, 0), 0);
+}
+
+#[test]
+fn scrolls_up() {
+    // LD I, 0x0 / LD V1, 0x03 / DRW V0, V1, 1 / SCU 2
+    let mut cpu = cpu_with_program(&[0xA0, 0x00, 0x61, 0x03, 0xD0, 0x11, 0x00, 0xD2]);
+
+    run_steps(&mut cpu, 4);
+
+    assert!(cpu.framebuffer.get_pixel(0, 1));
+    assert!(!cpu.framebuffer.get_pixel(0, 3));
+}
+
+#[test]
+fn loads_audio_pattern_and_pitch() {
+    // LD I, 0x300 / AUDIO / LD V4, 0x70 / PITCH V4
+    let mut cpu = cpu_with_program(&[0xA3, 0x00, 0xF0, 0x02, 0x64, 0x70, 0xF4, 0x3A]);
+    for (offset, byte) in cpu.memory[0x300..0x310].iter_mut().enumerate() {
+        *byte = offset as u8;
+    }
+
+    run_steps(&mut cpu, 4);
+
+    assert_eq!(cpu.audio_pattern, (0..16).collect::<Vec<u8>>());
+    assert_eq!(cpu.pitch, 0x70);
+}
+
+#[test]
+fn plays_audio_patterns() {
+    let mut wave = SquareWave::new(440.0, 4000);
+    wave.set_pattern(&[0xAA; 16], 64);
+    wave.beeping = true;
+
+    // At pitch 64 one bit lasts exactly one sample at 4000 Hz.
+    let samples: Vec<f32> = (0..4).map(|_| wave.next_sample()).collect();
+
+    assert!(samples[0] > 0.0 && samples[1] < 0.0 && samples[2] > 0.0 && samples[3] < 0.0);
+}
+
+#[test]
+fn xo_chip_rom_gets_64k_of_memory() {
+    let mut cpu = cpu_with_program(&[]);
+    cpu.quirks.xo_chip = true;
+
+    cpu.load_program(std::path::Path::new("roms/BRIX.ch8")).unwrap();
+
+    assert!(cpu.memory.len() >= 0x10000);
+}
+
+#[test]
+fn palette_from_config() {
+    let config = Config::parse("[palette]\ncolor0 = #102030\ncolor3 = FFEEDD\n").unwrap();
+    let palette = Palette::from_config(&config).unwrap();
+
+    assert_eq!(palette.color(0), 0x102030);
+    assert_eq!(palette.color(3), 0xFFEEDD);
+    assert_eq!(palette.color(1), 0xFFFFFF);
+    assert!(Palette::from_config(&Config::parse("[palette]\ncolor16 = #000000").unwrap()).is_err());
+}
+
+#[test]
+fn palette_themes() {
+    let config = Config::parse("[palette]\ntheme = amber\ncolor2 = #102030\n").unwrap();
+    let palette = Palette::from_config(&config).unwrap();
+
+    assert_eq!(palette.color(0), 0x140A00);
+    assert_eq!(palette.color(1), 0xFFB000);
+    assert_eq!(palette.color(2), 0x102030);
+    // The combinations of four planes fade towards the lit color.
+    assert_eq!(palette.color(15), 0xFFB000);
+    assert!(Palette::from_name("sepia").is_err());
+
+    let mut palette = Palette::from_name("LCD").unwrap();
+    palette.override_colors("#FFFFFF, #000000").unwrap();
+    assert_eq!(palette.colors[..3], [0xFFFFFF, 0x000000, 0x306230]);
+    assert!(palette.override_colors(&vec!["#000000"; 17].join(",")).is_err());
+}